@@ -0,0 +1,403 @@
+use num_traits::ToPrimitive;
+
+use crate::expression::{Expression, MathError};
+use crate::factor;
+use crate::fraction;
+use crate::inequality::{self, Interval};
+use crate::integrate;
+use crate::limit::{self, Direction};
+use crate::polynomial::Polynomial;
+use crate::series;
+use crate::settings::{self, Property};
+use crate::solve::{self, Solutions};
+use crate::trigsimp::{self, Form};
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power, Function, Complex, List};
+
+// Number of significant digits `evalf` shows when none are given.
+const DEFAULT_DIGITS: u32 = 15;
+
+// The order of the remainder term `series` stops at when none is given.
+const DEFAULT_SERIES_ORDER: u32 = 6;
+
+// A top-level request typed into the REPL. Plain expressions are simplified,
+// while named commands such as `evalf(sqrt(2), 50)` run other operations.
+pub enum Command {
+    Simplify(Expression),
+    Evalf(Expression, u32),
+    Polar(Expression),
+    Expand(Expression),
+    Factor(Expression),
+    Together(Expression),
+    Cancel(Expression),
+    Apart(Expression, Option<Expression>),
+    Diff(Expression, Option<Expression>, u32),
+    Integrate(Expression, Option<Expression>, Option<(Expression, Expression)>),
+    Limit(Expression, Expression, Expression, Direction),
+    Series(Expression, Option<Expression>, Expression, u32),
+    Solve(Expression, Option<Expression>),
+    Domain(Expression, Option<Expression>),
+    Trigsimp(Expression),
+    Rewrite(Expression, Form),
+    Degree(Expression, Expression),
+    Coeff(Expression, Expression, u32),
+    Lcoeff(Expression, Expression),
+    Quo(Expression, Expression),
+    Rem(Expression, Expression),
+    Mode(bool),
+    Assume(Variable, Vec<Property>),
+    Forget(Variable),
+}
+
+impl Command {
+    pub fn is_command(name: &str) -> bool {
+        matches!(name, "evalf" | "polar" | "mode" | "expand" | "factor" | "together" | "cancel" | "apart" | "diff" | "integrate" | "limit" | "series" | "solve" | "domain"
+            | "trigsimp" | "rewrite" | "assume" | "forget"
+            | "degree" | "coeff" | "lcoeff" | "quo" | "rem")
+    }
+
+    pub fn new(name: &str, mut args: Vec<Expression>) -> Result<Command, String> {
+        match (name, args.len()) {
+            ("evalf", 1) => Ok(Command::Evalf(args.remove(0), DEFAULT_DIGITS)),
+            ("evalf", 2) => {
+                let digits = args.pop().unwrap();
+                match digits.simplify() {
+                    Ok(Expression::Integer(n)) => n.num()
+                        .to_u32()
+                        .filter(|d| *d > 0)
+                        .map(|d| Command::Evalf(args.remove(0), d))
+                        .ok_or_else(|| format!("Invalid number of digits `{}`", n.num())),
+                    _ => Err("The number of digits must be a positive integer".to_string()),
+                }
+            }
+            ("evalf", _) => Err("evalf expects an expression and an optional number of digits".to_string()),
+            ("expand", 1) => Ok(Command::Expand(args.remove(0))),
+            ("expand", _) => Err("expand expects a single expression".to_string()),
+            ("factor", 1) => Ok(Command::Factor(args.remove(0))),
+            ("factor", _) => Err("factor expects a single polynomial".to_string()),
+            ("together", 1) => Ok(Command::Together(args.remove(0))),
+            ("together", _) => Err("together expects a single expression".to_string()),
+            ("cancel", 1) => Ok(Command::Cancel(args.remove(0))),
+            ("cancel", _) => Err("cancel expects a single expression".to_string()),
+            ("apart", 1) => Ok(Command::Apart(args.remove(0), None)),
+            ("apart", 2) => Ok(Command::Apart(args.remove(0), args.pop())),
+            ("apart", _) => Err("apart expects a rational function and an optional variable".to_string()),
+            ("diff", 1) => Ok(Command::Diff(args.remove(0), None, 1)),
+            ("diff", 2) => Ok(Command::Diff(args.remove(0), args.pop(), 1)),
+            ("diff", 3) => {
+                let n = Command::natural(args.pop().unwrap(), "order")?;
+                Ok(Command::Diff(args.remove(0), args.pop(), n))
+            }
+            ("diff", _) => Err("diff expects an expression, an optional variable and an optional order".to_string()),
+            ("integrate", 1) => Ok(Command::Integrate(args.remove(0), None, None)),
+            ("integrate", 2) => Ok(Command::Integrate(args.remove(0), args.pop(), None)),
+            ("integrate", 4) => {
+                let (b, a) = (args.pop().unwrap(), args.pop().unwrap());
+                Ok(Command::Integrate(args.remove(0), args.pop(), Some((a, b))))
+            }
+            ("integrate", _) => Err("integrate expects an expression, an optional variable and optional bounds".to_string()),
+            ("limit", 3) => Ok(Command::Limit(args.remove(0), args.remove(0), args.remove(0), Direction::Both)),
+            ("limit", 4) => {
+                let direction = match args.pop().unwrap().simplify() {
+                    Ok(Expression::Variable(v)) if v.as_str() == "left" => Direction::Left,
+                    Ok(Expression::Variable(v)) if v.as_str() == "right" => Direction::Right,
+                    _ => return Err("The direction of a limit must be either `left` or `right`".to_string()),
+                };
+                Ok(Command::Limit(args.remove(0), args.remove(0), args.remove(0), direction))
+            }
+            ("limit", _) => Err("limit expects an expression, a variable, a point and an optional direction".to_string()),
+            ("series", 1) => Ok(Command::Series(args.remove(0), None, int!(0), DEFAULT_SERIES_ORDER)),
+            ("series", 2) => Ok(Command::Series(args.remove(0), args.pop(), int!(0), DEFAULT_SERIES_ORDER)),
+            ("series", 3) => {
+                let point = args.pop().unwrap();
+                Ok(Command::Series(args.remove(0), args.pop(), point, DEFAULT_SERIES_ORDER))
+            }
+            ("series", 4) => {
+                let n = Command::natural(args.pop().unwrap(), "order")?;
+                let point = args.pop().unwrap();
+                Ok(Command::Series(args.remove(0), args.pop(), point, n))
+            }
+            ("series", _) => Err("series expects an expression, an optional variable, point and order".to_string()),
+            ("solve", 1) => Ok(Command::Solve(args.remove(0), None)),
+            ("solve", 2) => Ok(Command::Solve(args.remove(0), args.pop())),
+            ("solve", _) => Err("solve expects an equation, an inequality or a list of equations, and optional unknowns".to_string()),
+            ("domain", 1) => Ok(Command::Domain(args.remove(0), None)),
+            ("domain", 2) => Ok(Command::Domain(args.remove(0), args.pop())),
+            ("domain", _) => Err("domain expects an expression and an optional variable".to_string()),
+            ("trigsimp", 1) => Ok(Command::Trigsimp(args.remove(0))),
+            ("trigsimp", _) => Err("trigsimp expects a single expression".to_string()),
+            ("rewrite", 2) => match args.pop().unwrap().simplify() {
+                Ok(Expression::Variable(v)) if v.as_str() == "exp" => Ok(Command::Rewrite(args.remove(0), Form::Exp)),
+                Ok(Expression::Variable(v)) if v.as_str() == "trig" => Ok(Command::Rewrite(args.remove(0), Form::Trig)),
+                Ok(Expression::Variable(v)) if v.as_str() == "ln" => Ok(Command::Rewrite(args.remove(0), Form::Ln)),
+                _ => Err("rewrite expects an expression and one of `exp`, `trig` or `ln`".to_string()),
+            },
+            ("rewrite", _) => Err("rewrite expects an expression and one of `exp`, `trig` or `ln`".to_string()),
+            ("degree", 2) => Ok(Command::Degree(args.remove(0), args.remove(0))),
+            ("degree", _) => Err("degree expects a polynomial and a variable".to_string()),
+            ("coeff", 3) => {
+                let n = Command::natural(args.pop().unwrap(), "power")?;
+                Ok(Command::Coeff(args.remove(0), args.remove(0), n))
+            }
+            ("coeff", _) => Err("coeff expects a polynomial, a variable and a power".to_string()),
+            ("lcoeff", 2) => Ok(Command::Lcoeff(args.remove(0), args.remove(0))),
+            ("lcoeff", _) => Err("lcoeff expects a polynomial and a variable".to_string()),
+            ("quo", 2) => Ok(Command::Quo(args.remove(0), args.remove(0))),
+            ("quo", _) => Err("quo expects two polynomials".to_string()),
+            ("rem", 2) => Ok(Command::Rem(args.remove(0), args.remove(0))),
+            ("rem", _) => Err("rem expects two polynomials".to_string()),
+            ("polar", 1) => Ok(Command::Polar(args.remove(0))),
+            ("polar", _) => Err("polar expects a single expression".to_string()),
+            ("mode", 1) => match args.remove(0).simplify() {
+                Ok(Expression::Variable(v)) if v.as_str() == "complex" => Ok(Command::Mode(true)),
+                Ok(Expression::Variable(v)) if v.as_str() == "real" => Ok(Command::Mode(false)),
+                _ => Err("mode expects either `real` or `complex`".to_string()),
+            },
+            ("mode", _) => Err("mode expects either `real` or `complex`".to_string()),
+            ("assume", 2..) => {
+                let x = Command::symbol(args.remove(0))?;
+                let properties = args.into_iter()
+                    .map(|p| match p.simplify() {
+                        Ok(Expression::Variable(v)) => Property::from_name(v.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("The properties are `real`, `integer`, `nonzero`, `nonnegative` and `positive`")?;
+                Ok(Command::Assume(x, properties))
+            }
+            ("assume", _) => Err("assume expects a variable and properties such as `positive`".to_string()),
+            ("forget", 1) => Ok(Command::Forget(Command::symbol(args.remove(0))?)),
+            ("forget", _) => Err("forget expects a variable".to_string()),
+            (name, _) => Err(format!("Unknown command `{}`", name)),
+        }
+    }
+
+    pub fn run(self) -> Result<String, MathError> {
+        match self {
+            Command::Simplify(u) => Ok(u.simplify()?.to_string()),
+            Command::Evalf(u, digits) => Command::evalf(u.simplify()?, digits),
+            Command::Expand(u) => Ok(u.simplify()?.expand()?.to_string()),
+            Command::Factor(u) => Ok(factor::factor(u)?.to_string()),
+            Command::Together(u) => Ok(fraction::together(u)?.to_string()),
+            Command::Cancel(u) => Ok(fraction::cancel(u)?.to_string()),
+            Command::Apart(u, x) => {
+                let x = Command::variable(&u.clone().simplify()?, x)?;
+                Ok(fraction::apart(u, x)?.to_string())
+            }
+            Command::Integrate(u, x, bounds) => {
+                let x = Command::variable(&u.clone().simplify()?, x)?;
+                match bounds {
+                    Some((a, b)) => Ok(integrate::integrate_between(u, &x, a, b)?.to_string()),
+                    None => Ok(integrate::integrate(u, &x)?.to_string()),
+                }
+            }
+            Command::Limit(u, x, point, direction) => {
+                let x = Command::variable(&u.clone().simplify()?, Some(x))?;
+                Ok(limit::limit(u, &x, point, direction)?.to_string())
+            }
+            Command::Series(u, x, point, n) => {
+                let x = Command::variable(&u.clone().simplify()?, x)?;
+                Ok(series::series(u, &x, point, n)?.to_string())
+            }
+            Command::Solve(u, x) => Command::solve(u.simplify()?, x),
+            Command::Trigsimp(u) => Ok(trigsimp::trigsimp(u)?.to_string()),
+            Command::Rewrite(u, form) => Ok(trigsimp::rewrite(u, form)?.to_string()),
+            Command::Domain(u, x) => {
+                // The variable is read before simplifying, which may cancel it as in `x/x`.
+                let x = Command::variable(&u, x)?;
+                match inequality::domain(&u, &x)? {
+                    intervals if intervals.is_empty() => Ok(format!("The expression is undefined for every real `{}`", x)),
+                    intervals => Ok(Command::union(&intervals)),
+                }
+            }
+            Command::Diff(u, x, n) => {
+                let mut u = u.simplify()?;
+                let x = Command::variable(&u, x)?;
+                for _ in 0..n {
+                    u = u.derivative(&x)?;
+                }
+                Ok(u.to_string())
+            }
+            Command::Degree(u, x) => {
+                let (p, k) = Command::polynomial_in(u, x)?;
+                Ok(p.degree(k).to_string())
+            }
+            Command::Coeff(u, x, n) => {
+                let (p, k) = Command::polynomial_in(u, x)?;
+                Ok(p.coefficient_in(k, n).to_expression().to_string())
+            }
+            Command::Lcoeff(u, x) => {
+                let (p, k) = Command::polynomial_in(u, x)?;
+                Ok(p.coefficient_in(k, p.degree(k)).to_expression().to_string())
+            }
+            Command::Quo(u, v) => {
+                let [p, q] = Command::polynomials([u, v])?;
+                Ok(p.div_rem(&q)?.0.to_expression().to_string())
+            }
+            Command::Rem(u, v) => {
+                let [p, q] = Command::polynomials([u, v])?;
+                Ok(p.div_rem(&q)?.1.to_expression().to_string())
+            }
+            Command::Polar(u) => Command::polar(u.simplify()?).map(|u| u.to_string()),
+            Command::Mode(complex) => {
+                settings::set_complex_mode(complex);
+                Ok(format!("Switched to {} mode", if complex { "complex" } else { "real" }))
+            }
+            Command::Assume(x, properties) => {
+                properties.iter().for_each(|p| settings::assume(x.as_str(), *p));
+                let names: Vec<_> = properties.iter().map(|p| p.to_string()).collect();
+                Ok(format!("Assuming {} `{}`", names.join(" and "), x.as_str()))
+            }
+            Command::Forget(x) => {
+                settings::forget(x.as_str());
+                Ok(format!("Forgot the assumptions on `{}`", x.as_str()))
+            }
+        }
+    }
+
+    fn evalf(u: Expression, digits: u32) -> Result<String, MathError> {
+        let z = match Complex::split(&u)? {
+            Some(z) if z.im != int!(0) => z,
+            _ => return Ok(u.evalf(digits)?.to_decimal(digits)),
+        };
+
+        let im = z.im.evalf(digits)?;
+        if z.re == int!(0) {
+            return Ok(format!("{}*i", im.to_decimal(digits)))
+        }
+
+        let re = z.re.evalf(digits)?.to_decimal(digits);
+        match im.is_negative() {
+            true => Ok(format!("{} - {}*i", re, im.abs().to_decimal(digits))),
+            false => Ok(format!("{} + {}*i", re, im.to_decimal(digits))),
+        }
+    }
+
+    // Writes a complex value as r * e^(i*theta) with r = |u| and theta = arg(u).
+    fn polar(u: Expression) -> Result<Expression, MathError> {
+        let Some(z) = Complex::split(&u)? else {
+            return Err(MathError::Undefined(format!("Cannot separate `{}` into real and imaginary parts", u)))
+        };
+
+        let r = sqrt!(sum!(pow!(z.re, int!(2)), pow!(z.im, int!(2)))).simplify()?;
+        match func!("arg"; u).simplify()? {
+            theta if theta == int!(0) => Ok(r),
+            // The exponential is left as it is, which would otherwise turn
+            // quarter turns back into 1, i, -1 or -i.
+            theta => {
+                let rotation = func!("exp"; prod!(theta, imag!()).simplify()?);
+                Ok(if r == int!(1) { rotation } else { prod!(r, rotation) })
+            }
+        }
+    }
+
+    // Solves a single equation or inequality for one unknown, or a list of
+    // linear equations for several.
+    fn solve(u: Expression, x: Option<Expression>) -> Result<String, MathError> {
+        if let Expression::Inequality(ref i) = u {
+            let x = Command::variable(&u, x)?;
+            return match inequality::solve_inequality(i, &x)? {
+                intervals if intervals.is_empty() => Ok("The inequality has no real solutions".to_string()),
+                intervals => Ok(Command::union(&intervals)),
+            }
+        }
+
+        let Expression::List(equations) = u else {
+            let x = Command::variable(&u, x)?;
+            return match solve::solve(u, &x)? {
+                Solutions::All => Ok(format!("Every value of `{}` is a solution", x)),
+                Solutions::Finite(roots) => Ok(Expression::List(List::new(roots)).to_string()),
+            }
+        };
+
+        let xs = match x.map(Expression::simplify).transpose()? {
+            Some(Expression::List(xs)) => xs.values().to_vec(),
+            Some(x) => vec![x],
+            None => Polynomial::variables(&Expression::List(equations.clone())),
+        };
+        if let Some(x) = xs.iter().find(|x| !matches!(x, Expression::Variable(_))) {
+            return Err(MathError::Undefined(format!("`{}` is not a variable", x)))
+        }
+
+        match solve::solve_system(equations.values(), &xs)? {
+            None => Ok("The system has no solutions".to_string()),
+            Some(bindings) if bindings.is_empty() => Ok("Every value of the unknowns is a solution".to_string()),
+            Some(bindings) => Ok(Expression::List(List::new(bindings)).to_string()),
+        }
+    }
+
+    fn union(intervals: &[Interval]) -> String {
+        intervals.iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(" U ")
+    }
+
+    fn natural(u: Expression, what: &str) -> Result<u32, String> {
+        match u.simplify() {
+            Ok(Expression::Integer(n)) => n.num()
+                .to_u32()
+                .ok_or_else(|| format!("Invalid {} `{}`", what, n.num())),
+            _ => Err(format!("The {} must be a non-negative integer", what)),
+        }
+    }
+
+    // A variable that assumptions can be made about, which excludes the
+    // constants e, pi, i and oo.
+    fn symbol(u: Expression) -> Result<Variable, String> {
+        match u.simplify() {
+            Ok(Expression::Variable(v)) if !matches!(v.as_str(), "e" | "pi" | "i" | "oo") => Ok(v),
+            _ => Err("Assumptions can only be made about variables".to_string()),
+        }
+    }
+
+    // Reads expressions as polynomials over all the variables they contain.
+    fn polynomials<const N: usize>(us: [Expression; N]) -> Result<[Polynomial; N], MathError> {
+        let mut expanded = Vec::new();
+        for u in us {
+            expanded.push(u.simplify()?.expand()?);
+        }
+
+        let mut vars: Vec<Expression> = expanded.iter().flat_map(Polynomial::variables).collect();
+        vars.sort();
+        vars.dedup();
+
+        let mut polynomials = Vec::new();
+        for u in expanded {
+            match Polynomial::from_expression(&u, &vars) {
+                Some(p) => polynomials.push(p),
+                None => return Err(MathError::Undefined(format!("`{}` is not a polynomial", u))),
+            }
+        }
+
+        Ok(polynomials.try_into().expect("one polynomial per expression"))
+    }
+
+    // Reads a polynomial along with the index of the variable `x` within it.
+    fn polynomial_in(u: Expression, x: Expression) -> Result<(Polynomial, usize), MathError> {
+        let x = x.simplify()?;
+        let [p, _] = Command::polynomials([u, x.clone()])?;
+
+        match p.vars().iter().position(|v| v == &x) {
+            Some(k) => Ok((p, k)),
+            None => Err(MathError::Undefined(format!("`{}` is not a variable", x))),
+        }
+    }
+
+    // The variable a command works with, which may be omitted when the
+    // expression has only one.
+    fn variable(u: &Expression, x: Option<Expression>) -> Result<Expression, MathError> {
+        let x = match x {
+            Some(x) => x.simplify()?,
+            None => match Polynomial::variables(u).as_slice() {
+                [x] => x.clone(),
+                [] => return Err(MathError::Undefined(format!("`{}` has no variables", u))),
+                _ => return Err(MathError::Undefined(format!("`{}` has several variables, so one must be given", u))),
+            },
+        };
+
+        match x {
+            Expression::Variable(_) => Ok(x),
+            _ => Err(MathError::Undefined(format!("`{}` is not a variable", x))),
+        }
+    }
+}
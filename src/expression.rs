@@ -2,17 +2,50 @@ use std::fmt;
 use std::cmp;
 use std::error::Error;
 
-use crate::traits::Simplify;
-use crate::types::{self, Integer, Product, Power, Sum};
+use num_bigint::Sign;
+
+use crate::float::Float;
+use crate::settings;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+use crate::types::{self, Integer, Product, Power, Sum, Function, Equation, Inequality, List};
+
+// How many times `evalf` may double its working precision to reach agreement.
+const EVALF_MAX_REFINEMENTS: u32 = 4;
+
+// Most significant digits `evalf` computes, so that a huge request reports
+// an error rather than hanging.
+const EVALF_MAX_DIGITS: u32 = 2_000;
+
+// Bits of precision used when a numerical value only decides a sign.
+const SIGN_PRECISION: u64 = 128;
 
 #[derive(Debug)]
-pub struct UndefinedError(pub String);
+pub enum MathError {
+    Undefined(String),
+    Overflow(String),
+    NoClosedForm(String),
+}
 
-impl Error for UndefinedError {}
+impl Error for MathError {}
 
-impl fmt::Display for UndefinedError {
+impl fmt::Display for MathError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            MathError::Undefined(s) => write!(f, "{}", s),
+            MathError::Overflow(s) => write!(f, "Overflow: {}", s),
+            MathError::NoClosedForm(s) => write!(f, "No closed form found for {}", s),
+        }
+    }
+}
+
+impl MathError {
+    // Rewrites the message, keeping the kind of error.
+    pub fn map(self, f: impl FnOnce(String) -> String) -> MathError {
+        match self {
+            MathError::Undefined(s) => MathError::Undefined(f(s)),
+            MathError::Overflow(s) => MathError::Overflow(f(s)),
+            MathError::NoClosedForm(s) => MathError::NoClosedForm(f(s)),
+        }
     }
 }
 
@@ -24,11 +57,14 @@ pub enum Expression {
     Sum(types::Sum),
     Product(types::Product),
     Power(types::Power),
-    Function(types::Function)
+    Function(types::Function),
+    Equation(types::Equation),
+    Inequality(types::Inequality),
+    List(types::List),
 }
 
 impl Expression {
-    pub fn simplify(self) -> Result<Expression, UndefinedError> {
+    pub fn simplify(self) -> Result<Expression, MathError> {
         match self {
             Expression::Integer(i)   => i.simplify(),
             Expression::Rational(r) => r.simplify(),
@@ -37,9 +73,211 @@ impl Expression {
             Expression::Sum(s)           => s.simplify(),
             Expression::Variable(v) => v.simplify(),
             Expression::Function(f) => f.simplify(),
+            Expression::Equation(e) => e.simplify(),
+            Expression::Inequality(e) => e.simplify(),
+            Expression::List(l) => l.simplify(),
+        }
+    }
+
+    // Distributes products over sums and multiplies out integer powers of
+    // sums, giving a sum of monomials. Expects a simplified expression.
+    pub fn expand(self) -> Result<Expression, MathError> {
+        match self {
+            Expression::Sum(s) => s.expand(),
+            Expression::Product(p) => p.expand(),
+            Expression::Power(p) => p.expand(),
+            Expression::Function(f) => f.expand(),
+            Expression::Equation(e) => e.expand(),
+            Expression::Inequality(e) => e.expand(),
+            Expression::List(l) => l.expand(),
+            u => Ok(u),
+        }
+    }
+
+    // Differentiates with respect to the variable x, treating every other
+    // symbol as a constant. Expects a simplified expression.
+    pub fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        match self {
+            Expression::Variable(v) => v.derivative(x),
+            Expression::Sum(s) => s.derivative(x),
+            Expression::Product(p) => p.derivative(x),
+            Expression::Power(p) => p.derivative(x),
+            Expression::Function(f) => f.derivative(x),
+            Expression::Equation(e) => e.derivative(x),
+            Expression::Inequality(e) => Err(MathError::Undefined(format!("Cannot differentiate `{}`", Expression::Inequality(e.clone())))),
+            Expression::List(l) => l.derivative(x),
+            Expression::Integer(_) | Expression::Rational(_) => Ok(int!(0)),
+        }
+    }
+
+    pub fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        match self {
+            Expression::Integer(i)   => i.evaluate(prec),
+            Expression::Rational(r) => r.evaluate(prec),
+            Expression::Product(p)   => p.evaluate(prec),
+            Expression::Power(p)       => p.evaluate(prec),
+            Expression::Sum(s)           => s.evaluate(prec),
+            Expression::Variable(v) => v.evaluate(prec),
+            Expression::Function(f) => f.evaluate(prec),
+            Expression::Equation(e) => e.evaluate(prec),
+            Expression::Inequality(e) => e.evaluate(prec),
+            Expression::List(l) => l.evaluate(prec),
+        }
+    }
+
+    // Numerically evaluates the expression to the given number of significant
+    // decimal digits, resolving the constants `e` and `pi`.
+    pub fn evalf(&self, digits: u32) -> Result<Float, MathError> {
+        if digits > EVALF_MAX_DIGITS {
+            return Err(MathError::Overflow(format!("Cannot evaluate to more than {} digits", EVALF_MAX_DIGITS)))
+        }
+
+        let mut prec = (digits as f64 * std::f64::consts::LOG2_10).ceil() as u64 + 32;
+        let mut result = self.evaluate(prec)?;
+
+        // Cancellation can eat into the working precision, so the precision is
+        // doubled until two evaluations agree on every requested digit.
+        for _ in 0..EVALF_MAX_REFINEMENTS {
+            prec *= 2;
+            let refined = self.evaluate(prec)?;
+            if refined.to_decimal(digits) == result.to_decimal(digits) {
+                return Ok(refined)
+            }
+            result = refined;
+        }
+
+        Ok(result)
+    }
+
+    pub fn free_of(&self, u: &Expression) -> bool {
+        if self == u {
+            return false
+        }
+
+        match self {
+            Expression::Sum(s) => s.values().iter().all(|v| v.free_of(u)),
+            Expression::Product(p) => p.values().iter().all(|v| v.free_of(u)),
+            Expression::Power(p) => p.base.free_of(u) && p.exp.free_of(u),
+            Expression::Function(f) => f.args().iter().all(|v| v.free_of(u)),
+            Expression::Equation(e) => e.lhs.free_of(u) && e.rhs.free_of(u),
+            Expression::Inequality(e) => e.lhs.free_of(u) && e.rhs.free_of(u),
+            Expression::List(l) => l.values().iter().all(|v| v.free_of(u)),
+            _ => true,
+        }
+    }
+
+    // Replaces every occurrence of `from` with `to`, leaving the result
+    // unsimplified.
+    pub fn substitute(&self, from: &Expression, to: &Expression) -> Expression {
+        if self == from {
+            return to.clone()
+        }
+
+        match self {
+            Expression::Sum(s) => Sum::new(s.values().iter().map(|v| v.substitute(from, to)).collect()).into(),
+            Expression::Product(p) => Product::new(p.values().iter().map(|v| v.substitute(from, to)).collect()).into(),
+            Expression::Power(p) => pow!(p.base.substitute(from, to), p.exp.substitute(from, to)),
+            Expression::Function(f) => Expression::Function(f.with_args(f.args().into_iter().map(|v| v.substitute(from, to)).collect())),
+            Expression::Equation(e) => Equation::new(e.lhs.substitute(from, to), e.rhs.substitute(from, to)).into(),
+            Expression::Inequality(e) => Inequality::new(e.lhs.substitute(from, to), e.relation, e.rhs.substitute(from, to)).into(),
+            Expression::List(l) => List::new(l.values().iter().map(|v| v.substitute(from, to)).collect()).into(),
+            u => u.clone(),
+        }
+    }
+
+    // The sign of a real expression that can be evaluated numerically.
+    pub fn sign(&self) -> Option<cmp::Ordering> {
+        match self {
+            Expression::Integer(n) => Some(n.num().sign().cmp(&Sign::NoSign)),
+            Expression::Rational(r) => Some(r.num().sign().cmp(&Sign::NoSign)),
+            u => u.evaluate(SIGN_PRECISION).ok().map(|x| x.cmp(&Float::from_int(0))),
+        }
+    }
+
+    // Whether u is positive for every value of its variables their
+    // assumptions allow. False means only that this is not known, as with
+    // the other predicates below.
+    pub fn is_positive(&self) -> bool {
+        if self.sign() == Some(cmp::Ordering::Greater) {
+            return true
+        }
+
+        match self {
+            Expression::Variable(v) => v.assumptions().positive,
+            Expression::Sum(s) => s.values().iter().all(Expression::is_positive),
+            Expression::Product(p) => p.values().iter().all(Expression::is_positive),
+            Expression::Power(p) => match p.exp.as_ref() {
+                Expression::Integer(n) if n.num().bit(0) => p.base.is_positive(),
+                Expression::Integer(_) => p.base.is_nonzero() && Function::is_real(&p.base).unwrap_or(false),
+                w => p.base.is_positive() && Function::is_real(w).unwrap_or(false),
+            },
+            Expression::Function(f) => match f {
+                Function::Exp(u) | Function::Cosh(u) => Function::is_real(u).unwrap_or(false),
+                Function::Sqrt(u) | Function::Cbrt(u) => u.is_positive(),
+                Function::Abs(u) => u.is_nonzero(),
+                _ => false,
+            },
+            _ => false,
         }
     }
 
+    pub fn is_nonnegative(&self) -> bool {
+        if self.is_positive() || self.sign() == Some(cmp::Ordering::Equal) {
+            return true
+        }
+
+        match self {
+            Expression::Variable(v) => v.assumptions().nonnegative,
+            Expression::Sum(s) => s.values().iter().all(Expression::is_nonnegative),
+            Expression::Product(p) => p.values().iter().all(Expression::is_nonnegative),
+            // Even powers of real values, and in real mode any power with an
+            // even numerator, such as `x^(2/3)`.
+            Expression::Power(p) => match p.exp.as_ref() {
+                Expression::Integer(n) if !n.num().bit(0) => Function::is_real(&p.base).unwrap_or(false),
+                Expression::Rational(q) if !q.num().bit(0) => !settings::complex_mode(),
+                w => p.base.is_nonnegative() && Function::is_real(w).unwrap_or(false),
+            },
+            Expression::Function(f) => match f {
+                Function::Abs(_) => true,
+                Function::Sqrt(u) => !settings::complex_mode() || u.is_nonnegative(),
+                Function::Cbrt(u) => u.is_nonnegative(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn is_nonzero(&self) -> bool {
+        if self.is_positive() || matches!(self.sign(), Some(cmp::Ordering::Less)) {
+            return true
+        }
+
+        match self {
+            Expression::Variable(v) => v.as_str() == "i" || v.assumptions().nonzero,
+            Expression::Product(p) => p.values().iter().all(Expression::is_nonzero),
+            Expression::Power(p) => p.base.is_nonzero(),
+            Expression::Function(Function::Exp(_)) => true,
+            Expression::Function(Function::Abs(u) | Function::Sqrt(u) | Function::Cbrt(u)) => u.is_nonzero(),
+            _ => false,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Expression::Integer(_) => true,
+            Expression::Variable(v) => v.assumptions().integer,
+            Expression::Sum(s) => s.values().iter().all(Expression::is_integer),
+            Expression::Product(p) => p.values().iter().all(Expression::is_integer),
+            Expression::Power(p) => p.base.is_integer() && p.exp.sign() != Some(cmp::Ordering::Less) && p.exp.is_integer(),
+            Expression::Function(Function::Abs(u)) => u.is_integer(),
+            _ => false,
+        }
+    }
+
+    pub fn is_imaginary_unit(&self) -> bool {
+        matches!(self, Expression::Variable(v) if v.as_str() == "i")
+    }
+
     pub fn base(&self) -> &Expression {
         match self {
             Expression::Power(p) => p.base.as_ref(),
@@ -59,6 +297,15 @@ impl cmp::Ord for Expression {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         use Expression as E;
         match (self, other) {
+            (E::List(a), E::List(b)) => a.cmp(b),
+            (E::List(_), _) => cmp::Ordering::Greater,
+            (_, E::List(_)) => cmp::Ordering::Less,
+            (E::Inequality(a), E::Inequality(b)) => a.cmp(b),
+            (E::Inequality(_), _) => cmp::Ordering::Greater,
+            (_, E::Inequality(_)) => cmp::Ordering::Less,
+            (E::Equation(a), E::Equation(b)) => a.cmp(b),
+            (E::Equation(_), _) => cmp::Ordering::Greater,
+            (_, E::Equation(_)) => cmp::Ordering::Less,
             (E::Integer(n), E::Integer(m)) => n.cmp(m),
             (E::Integer(_), _) => cmp::Ordering::Less,
             (_, E::Integer(_)) => cmp::Ordering::Greater,
@@ -104,7 +351,13 @@ impl fmt::Display for Expression {
                 .map(|e| format!("{}", e))
                 .collect::<Vec<_>>()
                 .join(" * ")),
-            Expression::Function(g) => write!(f, "{}", g)
+            Expression::Function(g) => write!(f, "{}", g),
+            Expression::Equation(e) => write!(f, "{} = {}", e.lhs, e.rhs),
+            Expression::Inequality(e) => write!(f, "{} {} {}", e.lhs, e.relation, e.rhs),
+            Expression::List(l) => write!(f, "{{{}}}", l.values().iter()
+                .map(|e| format!("{}", e))
+                .collect::<Vec<_>>()
+                .join(", ")),
         }
     }
 }
@@ -143,4 +396,47 @@ impl From<types::Power> for Expression {
     fn from(value: types::Power) -> Self {
         Expression::Power(value)
     }
-}
\ No newline at end of file
+}
+impl From<types::Equation> for Expression {
+    fn from(value: types::Equation) -> Self {
+        Expression::Equation(value)
+    }
+}
+
+impl From<types::List> for Expression {
+    fn from(value: types::List) -> Self {
+        Expression::List(value)
+    }
+}
+
+impl From<types::Inequality> for Expression {
+    fn from(value: types::Inequality) -> Self {
+        Expression::Inequality(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn evalf(text: &str, digits: u32) -> Result<String, MathError> {
+        parse(text).evalf(digits).map(|x| x.to_decimal(digits))
+    }
+
+    #[test]
+    fn evalf_gives_the_requested_digits() {
+        assert_eq!(evalf("pi", 30).unwrap(), "3.14159265358979323846264338328");
+        assert_eq!(evalf("sqrt(2)", 20).unwrap(), "1.4142135623730950488");
+        assert_eq!(evalf("e", 15).unwrap(), "2.71828182845905");
+        assert_eq!(evalf("ln(2)", 10).unwrap(), "0.6931471806");
+        assert_eq!(evalf("-1/7", 3).unwrap(), "-0.143");
+        assert_eq!(evalf("2^100", 5).unwrap(), "1.2677e30");
+    }
+
+    #[test]
+    fn evalf_rejects_free_variables_and_huge_precision() {
+        assert!(evalf("x + 1", 10).is_err());
+        assert!(matches!(evalf("2", EVALF_MAX_DIGITS + 1), Err(MathError::Overflow(_))));
+    }
+}
@@ -0,0 +1,525 @@
+use std::mem;
+
+use num_bigint::BigInt;
+use num_integer::{Integer as _, Roots};
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::polynomial::Polynomial;
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Product, Power};
+
+// Coefficients of a univariate polynomial, lowest degree first, with no
+// trailing zeros. The zero polynomial is the empty vector.
+type Poly = Vec<BigInt>;
+
+// Factors a polynomial in one variable over the integers, returning a
+// product of powers of irreducible polynomials with positive leading
+// coefficients, times a rational constant.
+pub fn factor(u: Expression) -> Result<Expression, MathError> {
+    let u = u.simplify()?.expand()?;
+
+    let vars = Polynomial::variables(&u);
+    let not_polynomial = || MathError::Undefined(format!("`{}` is not a polynomial in one variable", u));
+
+    if vars.len() != 1 {
+        return match vars.is_empty() {
+            true => Ok(u),
+            false => Err(not_polynomial()),
+        }
+    }
+    let p = Polynomial::from_expression(&u, &vars).ok_or_else(not_polynomial)?;
+
+    if p.degree(0) <= 1 {
+        return Ok(u)
+    }
+
+    let (unit, irreducibles) = factor_polynomial(&p);
+    let mut factors = vec![Polynomial::number(&unit)];
+    for (f, multiplicity) in irreducibles {
+        factors.push(pow!(f.to_expression(), int!(multiplicity)));
+    }
+
+    Product::new(factors).simplify()
+}
+
+// Factors a nonzero polynomial in a single variable into a rational constant
+// and irreducible primitive integer polynomials with positive leading
+// coefficients, each paired with its multiplicity.
+pub fn factor_polynomial(p: &Polynomial) -> (BigRational, Vec<(Polynomial, u32)>) {
+    // Clear denominators and split off the content, so that a primitive
+    // polynomial with a positive leading coefficient remains.
+    let coeffs: Vec<BigRational> = (0..=p.degree(0)).map(|k| p.coefficient(&[k])).collect();
+    let den = coeffs.iter().fold(BigInt::one(), |acc, c| acc.lcm(c.denom()));
+    let num: Poly = coeffs.iter().map(|c| (c * &den).to_integer()).collect();
+
+    let mut unit = content(&num);
+    if p.leading_coefficient().is_negative() {
+        unit = -unit;
+    }
+    let f: Poly = num.iter().map(|c| c / &unit).collect();
+
+    let mut factors = Vec::new();
+    for (g, multiplicity) in square_free(&f) {
+        for h in factor_square_free(&g) {
+            factors.push((to_polynomial(&h, p.vars()), multiplicity));
+        }
+    }
+
+    (BigRational::new(unit, den), factors)
+}
+
+fn to_polynomial(f: &Poly, vars: &[Expression]) -> Polynomial {
+    f.iter()
+        .enumerate()
+        .fold(Polynomial::zero(vars.to_vec()), |p, (k, c)| p.with_term(vec![k as u32], BigRational::from(c.clone())))
+}
+
+// Polynomials over the integers.
+
+fn trim(mut f: Poly) -> Poly {
+    while f.last().is_some_and(Zero::is_zero) {
+        f.pop();
+    }
+    f
+}
+
+fn degree(f: &Poly) -> usize {
+    f.len().saturating_sub(1)
+}
+
+fn content(f: &Poly) -> BigInt {
+    f.iter().fold(BigInt::zero(), |acc, c| acc.gcd(c))
+}
+
+// Divides out the content and makes the leading coefficient positive.
+fn primitive(f: &Poly) -> Poly {
+    if f.is_empty() {
+        return Vec::new()
+    }
+    let mut c = content(f);
+    if f.last().unwrap().is_negative() {
+        c = -c;
+    }
+    f.iter().map(|a| a / &c).collect()
+}
+
+fn derivative(f: &Poly) -> Poly {
+    trim(f.iter().enumerate().skip(1).map(|(k, c)| c * k).collect())
+}
+
+fn sub(f: &Poly, g: &Poly) -> Poly {
+    let mut h = vec![BigInt::zero(); f.len().max(g.len())];
+    for (k, c) in f.iter().enumerate() {
+        h[k] += c;
+    }
+    for (k, c) in g.iter().enumerate() {
+        h[k] -= c;
+    }
+    trim(h)
+}
+
+fn mul(f: &Poly, g: &Poly) -> Poly {
+    if f.is_empty() || g.is_empty() {
+        return Vec::new()
+    }
+    let mut h = vec![BigInt::zero(); f.len() + g.len() - 1];
+    for (i, a) in f.iter().enumerate() {
+        for (j, b) in g.iter().enumerate() {
+            h[i + j] += a * b;
+        }
+    }
+    trim(h)
+}
+
+// Divides f by g when the quotient has integer coefficients and the
+// remainder is zero.
+fn div_exact(f: &Poly, g: &Poly) -> Option<Poly> {
+    let mut r = f.clone();
+    if r.len() < g.len() {
+        return r.is_empty().then(Vec::new)
+    }
+
+    let lc = g.last().unwrap();
+    let mut q = vec![BigInt::zero(); r.len() - g.len() + 1];
+
+    while r.len() >= g.len() {
+        let (c, rem) = r.last().unwrap().div_rem(lc);
+        if !rem.is_zero() {
+            return None
+        }
+        let shift = r.len() - g.len();
+        for (k, b) in g.iter().enumerate() {
+            r[k + shift] -= &c * b;
+        }
+        q[shift] = c;
+        r = trim(r);
+    }
+
+    r.is_empty().then_some(q)
+}
+
+fn pseudo_rem(f: &Poly, g: &Poly) -> Poly {
+    let mut r = f.clone();
+    let lc = g.last().unwrap();
+
+    while !r.is_empty() && r.len() >= g.len() {
+        let c = r.last().unwrap().clone();
+        let shift = r.len() - g.len();
+        r = r.iter().map(|a| a * lc).collect();
+        for (k, b) in g.iter().enumerate() {
+            r[k + shift] -= &c * b;
+        }
+        r = trim(r);
+    }
+
+    r
+}
+
+// The primitive greatest common divisor, found through a primitive
+// polynomial remainder sequence.
+fn gcd(f: &Poly, g: &Poly) -> Poly {
+    let (mut a, mut b) = (primitive(f), primitive(g));
+    while !b.is_empty() {
+        let r = primitive(&pseudo_rem(&a, &b));
+        a = mem::replace(&mut b, r);
+    }
+    a
+}
+
+// Yun's algorithm, splitting a primitive polynomial into square-free
+// factors paired with their multiplicities.
+fn square_free(f: &Poly) -> Vec<(Poly, u32)> {
+    let df = derivative(f);
+    let a = gcd(f, &df);
+    let mut b = div_exact(f, &a).expect("gcd divides f");
+    let mut c = div_exact(&df, &a).expect("gcd divides f'");
+    let mut d = sub(&c, &derivative(&b));
+
+    let mut factors = Vec::new();
+    let mut i = 1;
+
+    while degree(&b) > 0 {
+        let a = match d.is_empty() {
+            true => primitive(&b),
+            false => gcd(&b, &d),
+        };
+        b = div_exact(&b, &a).expect("gcd divides b");
+        c = div_exact(&d, &a).expect("gcd divides d");
+        d = sub(&c, &derivative(&b));
+
+        if degree(&a) > 0 {
+            factors.push((a, i));
+        }
+        i += 1;
+    }
+
+    factors
+}
+
+// Factors a square-free primitive polynomial by factoring it modulo a
+// prime larger than any coefficient of a factor can be, then recombining
+// the modular factors into true factors over the integers.
+fn factor_square_free(f: &Poly) -> Vec<Poly> {
+    if degree(f) <= 1 {
+        return vec![f.clone()]
+    }
+
+    let lc = f.last().unwrap().clone();
+    let norm = f.iter().map(|c| c * c).sum::<BigInt>().sqrt() + 1;
+    let bound = (norm << degree(f)) * lc.abs() * 2;
+
+    let mut p = bound | BigInt::one();
+    let field = loop {
+        p += 2;
+        if !is_probable_prime(&p) || (&lc % &p).is_zero() {
+            continue;
+        }
+        let field = Field::new(p.clone());
+        let g = field.reduce(f);
+        if degree(&field.gcd(&g, &field.derivative(&g))) == 0 {
+            break field;
+        }
+    };
+
+    let mut rng = Rng::new(degree(f) as u64);
+    let g = field.monic(&field.reduce(f));
+    let mut modular = Vec::new();
+    for (h, d) in field.distinct_degree(&g) {
+        modular.extend(field.equal_degree(&h, d, &mut rng));
+    }
+
+    let mut f = f.clone();
+    let mut factors = Vec::new();
+    let mut size = 1;
+
+    while 2 * size <= modular.len() {
+        let lc = f.last().unwrap().clone();
+        let found = combinations(modular.len(), size).into_iter().find_map(|subset| {
+            let candidate = subset.iter().fold(vec![lc.clone()], |acc, &k| field.mul(&acc, &modular[k]));
+            let candidate = primitive(&field.symmetric(&candidate));
+            div_exact(&f, &candidate).map(|quotient| (subset, candidate, quotient))
+        });
+
+        match found {
+            Some((subset, candidate, quotient)) => {
+                factors.push(candidate);
+                f = primitive(&quotient);
+                for k in subset.into_iter().rev() {
+                    modular.remove(k);
+                }
+            }
+            None => size += 1,
+        }
+    }
+
+    factors.push(f);
+    factors
+}
+
+// All increasing index lists of the given size drawn from 0..n.
+fn combinations(n: usize, size: usize) -> Vec<Vec<usize>> {
+    let mut result = Vec::new();
+    let mut subset: Vec<usize> = (0..size).collect();
+
+    loop {
+        result.push(subset.clone());
+
+        let Some(k) = (0..size).rev().find(|&k| subset[k] < n - size + k) else {
+            return result
+        };
+        subset[k] += 1;
+        for j in k + 1..size {
+            subset[j] = subset[j - 1] + 1;
+        }
+    }
+}
+
+fn is_probable_prime(n: &BigInt) -> bool {
+    const WITNESSES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if n < &BigInt::from(2) {
+        return false
+    }
+    for w in WITNESSES {
+        if (n % w).is_zero() {
+            return n == &BigInt::from(w)
+        }
+    }
+
+    let n1: BigInt = n - 1;
+    let s = n1.trailing_zeros().unwrap_or(0);
+    let d = &n1 >> s;
+
+    WITNESSES.iter().all(|&w| {
+        let mut x = BigInt::from(w).modpow(&d, n);
+        if x.is_one() || x == n1 {
+            return true
+        }
+        for _ in 1..s {
+            x = x.modpow(&BigInt::from(2), n);
+            if x == n1 {
+                return true
+            }
+        }
+        false
+    })
+}
+
+// A small xorshift generator, so that factoring is deterministic.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: &BigInt) -> BigInt {
+        let words = n.bits() / 64 + 2;
+        let digits = (0..words).map(|_| self.next()).collect::<Vec<_>>();
+        let mut r = BigInt::zero();
+        for d in digits {
+            r = (r << 64) + d;
+        }
+        r % n
+    }
+}
+
+// Polynomials over the integers modulo a prime p, with coefficients kept in 0..p.
+struct Field {
+    p: BigInt,
+}
+
+impl Field {
+    fn new(p: BigInt) -> Field {
+        Field { p }
+    }
+
+    fn reduce(&self, f: &Poly) -> Poly {
+        trim(f.iter().map(|c| c.mod_floor(&self.p)).collect())
+    }
+
+    // Maps coefficients into the symmetric range (-p/2, p/2].
+    fn symmetric(&self, f: &Poly) -> Poly {
+        let half: BigInt = &self.p >> 1;
+        trim(f.iter().map(|c| if c > &half { c - &self.p } else { c.clone() }).collect())
+    }
+
+    fn inverse(&self, a: &BigInt) -> BigInt {
+        a.modpow(&(&self.p - 2), &self.p)
+    }
+
+    fn monic(&self, f: &Poly) -> Poly {
+        let inv = self.inverse(f.last().unwrap());
+        self.reduce(&f.iter().map(|c| c * &inv).collect())
+    }
+
+    fn derivative(&self, f: &Poly) -> Poly {
+        self.reduce(&derivative(f))
+    }
+
+    fn sub(&self, f: &Poly, g: &Poly) -> Poly {
+        self.reduce(&sub(f, g))
+    }
+
+    fn mul(&self, f: &Poly, g: &Poly) -> Poly {
+        self.reduce(&mul(f, g))
+    }
+
+    fn div_rem(&self, f: &Poly, g: &Poly) -> (Poly, Poly) {
+        let mut r = f.clone();
+        if r.len() < g.len() {
+            return (Vec::new(), r)
+        }
+
+        let inv = self.inverse(g.last().unwrap());
+        let mut q = vec![BigInt::zero(); r.len() - g.len() + 1];
+
+        while !r.is_empty() && r.len() >= g.len() {
+            let c = (r.last().unwrap() * &inv).mod_floor(&self.p);
+            let shift = r.len() - g.len();
+            for (k, b) in g.iter().enumerate() {
+                r[k + shift] = (&r[k + shift] - &c * b).mod_floor(&self.p);
+            }
+            q[shift] = c;
+            r = trim(r);
+        }
+
+        (trim(q), r)
+    }
+
+    fn rem(&self, f: &Poly, g: &Poly) -> Poly {
+        self.div_rem(f, g).1
+    }
+
+    fn gcd(&self, f: &Poly, g: &Poly) -> Poly {
+        let (mut a, mut b) = (f.clone(), g.clone());
+        while !b.is_empty() {
+            let r = self.rem(&a, &b);
+            a = mem::replace(&mut b, r);
+        }
+        match a.is_empty() {
+            true => a,
+            false => self.monic(&a),
+        }
+    }
+
+    fn pow_mod(&self, f: &Poly, e: &BigInt, m: &Poly) -> Poly {
+        let mut result = vec![BigInt::one()];
+        let base = self.rem(f, m);
+        for k in (0..e.bits()).rev() {
+            result = self.rem(&self.mul(&result, &result), m);
+            if e.bit(k) {
+                result = self.rem(&self.mul(&result, &base), m);
+            }
+        }
+        result
+    }
+
+    // Splits a square-free monic polynomial into products of the
+    // irreducible factors sharing each degree.
+    fn distinct_degree(&self, f: &Poly) -> Vec<(Poly, usize)> {
+        let x = vec![BigInt::zero(), BigInt::one()];
+        let mut f = f.clone();
+        let mut h = x.clone();
+        let mut d = 0;
+        let mut result = Vec::new();
+
+        while degree(&f) >= 2 * (d + 1) {
+            d += 1;
+            h = self.pow_mod(&h, &self.p, &f);
+            let g = self.gcd(&f, &self.sub(&h, &x));
+            if degree(&g) > 0 {
+                f = self.div_rem(&f, &g).0;
+                h = self.rem(&h, &f);
+                result.push((g, d));
+            }
+        }
+
+        if degree(&f) > 0 {
+            result.push((f.clone(), degree(&f)));
+        }
+        result
+    }
+
+    // Cantor-Zassenhaus splitting of a product of irreducible factors of degree d.
+    fn equal_degree(&self, f: &Poly, d: usize, rng: &mut Rng) -> Vec<Poly> {
+        if degree(f) <= d {
+            return vec![f.clone()]
+        }
+
+        let e: BigInt = (self.p.pow(d as u32) - 1) >> 1;
+        loop {
+            let a = trim((0..degree(f)).map(|_| rng.below(&self.p)).collect());
+            if degree(&a) == 0 {
+                continue;
+            }
+
+            let b = self.sub(&self.pow_mod(&a, &e, f), &vec![BigInt::one()]);
+            let g = self.gcd(f, &b);
+            if degree(&g) > 0 && degree(&g) < degree(f) {
+                let mut factors = self.equal_degree(&g, d, rng);
+                factors.extend(self.equal_degree(&self.div_rem(f, &g).0, d, rng));
+                return factors
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn splits_into_irreducible_factors() {
+        assert_eq!(factor(parse("x^2 - 1")).unwrap(), parse("(x - 1)(x + 1)"));
+        assert_eq!(factor(parse("x^3 - 3x^2 + 3x - 1")).unwrap(), parse("(x - 1)^3"));
+        assert_eq!(factor(parse("x^4 - 1")).unwrap(), parse("(x - 1)(x + 1)(x^2 + 1)"));
+    }
+
+    #[test]
+    fn keeps_the_rational_content_outside() {
+        assert_eq!(factor(parse("2/3 x^2 - 2/3")).unwrap(), parse("2/3 (x - 1)(x + 1)"));
+        assert_eq!(factor(parse("-x^2 + 4")).unwrap(), parse("-(x - 2)(x + 2)"));
+    }
+
+    #[test]
+    fn leaves_irreducible_polynomials_alone() {
+        assert_eq!(factor(parse("x^4 + 1")).unwrap(), parse("x^4 + 1"));
+        assert_eq!(factor(parse("x^2 + x + 1")).unwrap(), parse("x^2 + x + 1"));
+    }
+
+    #[test]
+    fn rejects_several_variables() {
+        assert!(factor(parse("x^2 - y^2")).is_err());
+    }
+}
@@ -0,0 +1,564 @@
+use std::cmp;
+use std::ops::Neg;
+
+use num_bigint::BigInt;
+use num_integer::{Integer, Roots};
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::expression::MathError;
+
+// Extra bits carried through every internal computation so that the
+// rounding of intermediate results does not show up in the final digits.
+const GUARD_BITS: u64 = 24;
+
+// Number of argument halvings applied before summing the arctangent series.
+const ATAN_HALVINGS: u32 = 8;
+
+// The largest number of bits in the integer part of an angle given to sin
+// and cos, whose reduction by multiples of pi needs as many extra bits.
+const MAX_ANGLE_BITS: i64 = 4096;
+
+// An arbitrary-precision binary floating point number equal to `mant * 2^exp`.
+// Every operation takes the precision of its result in bits.
+#[derive(Debug, Clone)]
+pub struct Float {
+    mant: BigInt,
+    exp: i64,
+}
+
+impl Float {
+    pub fn from_int(n: impl Into<BigInt>) -> Float {
+        Float { mant: n.into(), exp: 0 }
+    }
+
+    pub fn from_ratio(num: &BigInt, den: &BigInt, prec: u64) -> Result<Float, MathError> {
+        Float::from_int(num.clone()).div(&Float::from_int(den.clone()), prec)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mant.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mant.is_negative()
+    }
+
+    pub fn abs(&self) -> Float {
+        Float { mant: self.mant.abs(), exp: self.exp }
+    }
+
+    // The position of the highest set bit, so that `2^(top - 1) <= |x| < 2^top`.
+    pub fn top(&self) -> i64 {
+        self.mant.bits() as i64 + self.exp
+    }
+
+    fn with_exp(&self, exp: i64) -> Float {
+        Float { mant: self.mant.clone(), exp: self.exp + exp }
+    }
+
+    fn round(self, prec: u64) -> Float {
+        let bits = self.mant.bits();
+        if bits <= prec {
+            return self
+        }
+
+        let shift = bits - prec;
+        let half = BigInt::one() << (shift - 1);
+        let mag = (self.mant.magnitude() + half.magnitude()) >> shift;
+        let mant = match self.mant.is_negative() {
+            true => -BigInt::from(mag),
+            false => BigInt::from(mag),
+        };
+
+        Float { mant, exp: self.exp + shift as i64 }
+    }
+
+    pub fn add(&self, other: &Float, prec: u64) -> Float {
+        if self.is_zero() {
+            return other.clone().round(prec)
+        }
+        if other.is_zero() {
+            return self.clone().round(prec)
+        }
+
+        // An operand entirely below the precision of the other cannot
+        // change the rounded result, so it is skipped rather than aligned.
+        let window = prec as i64 + 2;
+        if other.top() < self.top() - window {
+            return self.clone().round(prec)
+        }
+        if self.top() < other.top() - window {
+            return other.clone().round(prec)
+        }
+
+        let exp = cmp::min(self.exp, other.exp);
+        let mant = (&self.mant << (self.exp - exp) as u64) + (&other.mant << (other.exp - exp) as u64);
+        Float { mant, exp }.round(prec)
+    }
+
+    pub fn sub(&self, other: &Float, prec: u64) -> Float {
+        self.add(&-other.clone(), prec)
+    }
+
+    pub fn mul(&self, other: &Float, prec: u64) -> Float {
+        Float { mant: &self.mant * &other.mant, exp: self.exp + other.exp }.round(prec)
+    }
+
+    pub fn div(&self, other: &Float, prec: u64) -> Result<Float, MathError> {
+        if other.is_zero() {
+            return Err(MathError::Undefined("Indeterminate form: k/0".to_string()))
+        }
+
+        let shift = cmp::max(0, prec as i64 + other.mant.bits() as i64 - self.mant.bits() as i64 + 2) as u64;
+        let mant = (&self.mant << shift) / &other.mant;
+        Ok(Float { mant, exp: self.exp - other.exp - shift as i64 }.round(prec))
+    }
+
+    pub fn sqrt(&self, prec: u64) -> Result<Float, MathError> {
+        if self.is_negative() {
+            return Err(MathError::Undefined("Negative value under even root".to_string()))
+        }
+        if self.is_zero() {
+            return Ok(self.clone())
+        }
+
+        let mut shift = cmp::max(0, 2 * prec as i64 - self.mant.bits() as i64 + 2);
+        if (self.exp - shift) % 2 != 0 {
+            shift += 1;
+        }
+
+        let mant = (&self.mant << shift as u64).sqrt();
+        Ok(Float { mant, exp: (self.exp - shift) / 2 }.round(prec))
+    }
+
+    pub fn powi(&self, n: &BigInt, prec: u64) -> Result<Float, MathError> {
+        if n.is_negative() {
+            let wp = prec + GUARD_BITS;
+            return Float::from_int(1).div(&self.powi(&-n, wp)?, prec)
+        }
+
+        // The result needs roughly `n * top` bits of exponent, which has to
+        // fit comfortably within the exponent's own range.
+        let bound = n.to_i64()
+            .and_then(|n| n.checked_mul(self.top().abs() + 1))
+            .filter(|bits| *bits < i64::MAX / 4);
+
+        if bound.is_none() && !self.is_zero() && self.abs() != Float::from_int(1) {
+            return Err(MathError::Overflow(format!("power with exponent {} is too large to evaluate", n)))
+        }
+
+        let wp = prec + n.bits() + GUARD_BITS;
+        let mut result = Float::from_int(1);
+        let mut base = self.clone();
+        let mut n = n.clone();
+
+        while !n.is_zero() {
+            if n.bit(0) {
+                result = result.mul(&base, wp);
+            }
+            n >>= 1;
+            if !n.is_zero() {
+                base = base.mul(&base, wp);
+            }
+        }
+
+        Ok(result.round(prec))
+    }
+
+    pub fn exp(&self, prec: u64) -> Result<Float, MathError> {
+        if self.is_zero() {
+            return Ok(Float::from_int(1))
+        }
+
+        if self.top() > 60 {
+            return Err(MathError::Overflow(format!("exponent {} is too large to evaluate", self.to_decimal(6))))
+        }
+
+        // Reduce to x = k*ln(2) + r with |r| <= ln(2)/2, then scale r down
+        // further so that the Taylor series converges quickly, squaring
+        // the result back up afterwards.
+        let squarings = ((prec as f64).sqrt() / 2.0) as u64 + 1;
+        let wp = prec + squarings + GUARD_BITS + cmp::max(self.top(), 0) as u64;
+
+        let ln2 = Float::ln2(wp);
+        let k = self.div(&ln2, wp)?.round_to_int();
+        let r = self.sub(&ln2.mul(&Float::from_int(k.clone()), wp), wp).with_exp(-(squarings as i64));
+
+        let mut sum = Float::from_int(1);
+        let mut term = Float::from_int(1);
+        let mut n = 1u64;
+
+        loop {
+            term = term.mul(&r, wp).div(&Float::from_int(n), wp)?;
+            if term.is_zero() || term.top() < -(wp as i64) {
+                break;
+            }
+            sum = sum.add(&term, wp);
+            n += 1;
+        }
+
+        for _ in 0..squarings {
+            sum = sum.mul(&sum, wp);
+        }
+
+        Ok(sum.with_exp(k.to_i64().unwrap_or_default()).round(prec))
+    }
+
+    pub fn ln(&self, prec: u64) -> Result<Float, MathError> {
+        if self.is_negative() || self.is_zero() {
+            return Err(MathError::Undefined("negative or zero logarithm".to_string()))
+        }
+
+        // Write x = m * 2^e with m in [3/4, 3/2), so that ln(x) = ln(m) + e*ln(2)
+        // and ln(m) = 2*atanh((m - 1)/(m + 1)) converges quickly.
+        let mut e = self.top();
+        let mut m = self.with_exp(-e);
+        if m.mul(&Float::from_int(4), prec) < Float::from_int(3) {
+            m = m.with_exp(1);
+            e -= 1;
+        }
+
+        let wp = prec + GUARD_BITS;
+        let one = Float::from_int(1);
+        let z = m.sub(&one, wp).div(&m.add(&one, wp), wp)?;
+        let ln_m = Float::arctan_series(&z, wp, false)?.with_exp(1);
+
+        if e == 0 {
+            return Ok(ln_m.round(prec))
+        }
+
+        let wp = wp + 64;
+        Ok(ln_m.add(&Float::ln2(wp).mul(&Float::from_int(e), wp), prec))
+    }
+
+    pub fn pow(&self, y: &Float, prec: u64) -> Result<Float, MathError> {
+        match (self.is_zero(), y.is_negative() || y.is_zero()) {
+            (true, false) => return Ok(self.clone()),
+            (true, true) => return Err(MathError::Undefined("Indeterminate form: 0^0".to_string())),
+            _ => (),
+        }
+
+        if self.is_negative() {
+            return Err(MathError::Undefined("Negative base with a non-integer exponent".to_string()))
+        }
+
+        let wp = prec + GUARD_BITS + cmp::max(y.top(), 0) as u64;
+        y.mul(&self.ln(wp)?, wp).exp(prec)
+    }
+
+    pub fn atan(&self, prec: u64) -> Result<Float, MathError> {
+        if self.is_zero() {
+            return Ok(self.clone())
+        }
+
+        let wp = prec + GUARD_BITS;
+        let one = Float::from_int(1);
+
+        // atan(x) = pi/2 - atan(1/x) for x > 1, and symmetrically for x < -1.
+        if self.abs() > one {
+            let half_pi = Float::pi(wp).with_exp(-1);
+            let rest = one.div(self, wp)?.atan(wp)?;
+            return Ok(match self.is_negative() {
+                true => (-half_pi).sub(&rest, prec),
+                false => half_pi.sub(&rest, prec),
+            })
+        }
+
+        // atan(x) = 2*atan(x/(1 + sqrt(1 + x^2))) shrinks the argument so
+        // that the series converges quickly.
+        let mut x = self.clone();
+        for _ in 0..ATAN_HALVINGS {
+            let root = one.add(&x.mul(&x, wp), wp).sqrt(wp)?;
+            x = x.div(&one.add(&root, wp), wp)?;
+        }
+
+        Ok(Float::arctan_series(&x, wp, true)?.with_exp(ATAN_HALVINGS as i64).round(prec))
+    }
+
+    pub fn sin(&self, prec: u64) -> Result<Float, MathError> {
+        self.sin_cos(prec).map(|(sin, _)| sin)
+    }
+
+    pub fn cos(&self, prec: u64) -> Result<Float, MathError> {
+        self.sin_cos(prec).map(|(_, cos)| cos)
+    }
+
+    fn sin_cos(&self, prec: u64) -> Result<(Float, Float), MathError> {
+        if self.top() > MAX_ANGLE_BITS {
+            return Err(MathError::Overflow(format!("angle {} is too large to evaluate", self.to_decimal(6))))
+        }
+
+        // Reduce to x = k*pi/2 + r with |r| <= pi/4, where the Taylor series
+        // converge quickly, and rotate the result by k quarter turns.
+        let wp = prec + GUARD_BITS + cmp::max(self.top(), 0) as u64;
+        let half_pi = Float::pi(wp).with_exp(-1);
+        let k = self.div(&half_pi, wp)?.round_to_int();
+        let r = self.sub(&half_pi.mul(&Float::from_int(k.clone()), wp), wp);
+        let r2 = r.mul(&r, wp);
+
+        let mut sin = r.clone();
+        let mut cos = Float::from_int(1);
+        let mut term = Float::from_int(1);
+        let mut n = 1u64;
+
+        loop {
+            // term = (-1)^n r^(2n) / (2n)!, then the next odd term follows.
+            term = (-term.mul(&r2, wp)).div(&Float::from_int((2 * n - 1) * (2 * n)), wp)?;
+            if term.is_zero() || term.top() < -(wp as i64) {
+                break;
+            }
+            cos = cos.add(&term, wp);
+            sin = sin.add(&term.mul(&r, wp).div(&Float::from_int(2 * n + 1), wp)?, wp);
+            n += 1;
+        }
+
+        let (sin, cos) = (sin.round(prec), cos.round(prec));
+        Ok(match k.mod_floor(&BigInt::from(4)).to_u8() {
+            Some(0) => (sin, cos),
+            Some(1) => (cos, -sin),
+            Some(2) => (-sin, -cos),
+            _ => (-cos, sin),
+        })
+    }
+
+    pub fn asin(&self, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS;
+        let root = Float::from_int(1).sub(&self.mul(self, wp), wp);
+        if root.is_negative() {
+            return Err(MathError::Undefined("asin is only defined on [-1, 1]".to_string()))
+        }
+        Float::atan2(self, &root.sqrt(wp)?, prec)
+    }
+
+    pub fn acos(&self, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS;
+        let root = Float::from_int(1).sub(&self.mul(self, wp), wp);
+        if root.is_negative() {
+            return Err(MathError::Undefined("acos is only defined on [-1, 1]".to_string()))
+        }
+        Float::atan2(&root.sqrt(wp)?, self, prec)
+    }
+
+    pub fn sinh(&self, prec: u64) -> Result<Float, MathError> {
+        // e^x - e^-x cancels for small x, which costs as many bits as x is small.
+        let wp = prec + GUARD_BITS + cmp::max(-self.top(), 0) as u64;
+        let ex = self.exp(wp)?;
+        Ok(ex.sub(&Float::from_int(1).div(&ex, wp)?, wp).with_exp(-1).round(prec))
+    }
+
+    pub fn cosh(&self, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS;
+        let ex = self.exp(wp)?;
+        Ok(ex.add(&Float::from_int(1).div(&ex, wp)?, wp).with_exp(-1).round(prec))
+    }
+
+    pub fn tanh(&self, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS;
+        self.sinh(wp)?.div(&self.cosh(wp)?, prec)
+    }
+
+    pub fn asinh(&self, prec: u64) -> Result<Float, MathError> {
+        // asinh(x) = ln(|x| + sqrt(x^2 + 1)) with the sign of x, where the
+        // logarithm of a number near 1 again costs bits for small x.
+        let wp = prec + GUARD_BITS + cmp::max(-self.top(), 0) as u64;
+        let x = self.abs();
+        let root = x.mul(&x, wp).add(&Float::from_int(1), wp).sqrt(wp)?;
+        let result = x.add(&root, wp).ln(prec)?;
+        Ok(if self.is_negative() { -result } else { result })
+    }
+
+    pub fn acosh(&self, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS;
+        let one = Float::from_int(1);
+        if self < &one {
+            return Err(MathError::Undefined("acosh is only defined from 1 onwards".to_string()))
+        }
+        let root = self.mul(self, wp).sub(&one, wp).sqrt(wp)?;
+        self.add(&root, wp).ln(prec)
+    }
+
+    pub fn atanh(&self, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS + cmp::max(-self.top(), 0) as u64;
+        let one = Float::from_int(1);
+        if self.abs() >= one {
+            return Err(MathError::Undefined("atanh is only defined on (-1, 1)".to_string()))
+        }
+        let ratio = one.add(self, wp).div(&one.sub(self, wp), wp)?;
+        Ok(ratio.ln(prec + 1)?.with_exp(-1).round(prec))
+    }
+
+    // Whether x lies within 2^-bits of an integer, giving that integer.
+    pub fn near_integer(&self, bits: u64) -> Option<BigInt> {
+        let n = self.round_to_int();
+        let distance = self.sub(&Float::from_int(n.clone()), bits + 64).abs();
+        (distance.is_zero() || distance.top() < -(bits as i64)).then_some(n)
+    }
+
+    // The angle of the point (x, y) in (-pi, pi].
+    pub fn atan2(y: &Float, x: &Float, prec: u64) -> Result<Float, MathError> {
+        let wp = prec + GUARD_BITS;
+        match (x.is_zero(), x.is_negative()) {
+            (true, _) if y.is_zero()
+                => Err(MathError::Undefined("The argument of zero is undefined".to_string())),
+            (true, _) => {
+                let half_pi = Float::pi(prec).with_exp(-1);
+                Ok(if y.is_negative() { -half_pi } else { half_pi })
+            }
+            (false, false) => y.div(x, wp)?.atan(prec),
+            (false, true) => {
+                let angle = y.div(x, wp)?.atan(wp)?;
+                Ok(match y.is_negative() {
+                    true => angle.sub(&Float::pi(wp), prec),
+                    false => angle.add(&Float::pi(wp), prec),
+                })
+            }
+        }
+    }
+
+    pub fn ln2(prec: u64) -> Float {
+        // ln(2) = 2*atanh(1/3)
+        let wp = prec + GUARD_BITS;
+        let third = Float::from_int(1).div(&Float::from_int(3), wp).expect("nonzero divisor");
+        Float::arctan_series(&third, wp, false)
+            .expect("series of a nonzero argument")
+            .with_exp(1)
+            .round(prec)
+    }
+
+    pub fn pi(prec: u64) -> Float {
+        // Machin's formula: pi = 16*atan(1/5) - 4*atan(1/239)
+        let wp = prec + GUARD_BITS;
+        let atan_inv = |n: i32| {
+            let x = Float::from_int(1).div(&Float::from_int(n), wp).expect("nonzero divisor");
+            Float::arctan_series(&x, wp, true).expect("series of a nonzero argument")
+        };
+        atan_inv(5).with_exp(4).sub(&atan_inv(239).with_exp(2), prec)
+    }
+
+    pub fn e(prec: u64) -> Float {
+        Float::from_int(1).exp(prec).expect("exp(1) is finite")
+    }
+
+    // Sums x - x^3/3 + x^5/5 - ... for atan(x), or the same series without
+    // alternating signs for atanh(x). Only meant for small |x|.
+    fn arctan_series(x: &Float, prec: u64, alternating: bool) -> Result<Float, MathError> {
+        let x2 = x.mul(x, prec);
+        let mut power = x.clone();
+        let mut sum = x.clone();
+        let mut n = 1u64;
+
+        loop {
+            power = power.mul(&x2, prec);
+            if alternating {
+                power = -power;
+            }
+            n += 2;
+
+            let term = power.div(&Float::from_int(n), prec)?;
+            if term.is_zero() || term.top() < sum.top() - prec as i64 - 2 {
+                break;
+            }
+            sum = sum.add(&term, prec);
+        }
+
+        Ok(sum)
+    }
+
+    fn round_to_int(&self) -> BigInt {
+        if self.exp >= 0 {
+            return &self.mant << self.exp as u64
+        }
+
+        // Adding a half is done exactly, then the fraction bits are floored away.
+        let half = Float { mant: BigInt::one(), exp: -1 };
+        let exact = (cmp::max(self.top(), 0) - self.exp + 2) as u64;
+        let shifted = self.add(&half, exact);
+        match shifted.exp >= 0 {
+            true => &shifted.mant << shifted.exp as u64,
+            false => &shifted.mant >> (-shifted.exp) as u64,
+        }
+    }
+
+    // Formats the number with the given count of significant decimal digits.
+    pub fn to_decimal(&self, digits: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string()
+        }
+
+        let digits = cmp::max(digits, 1);
+        let lower = BigInt::from(10).pow(digits - 1);
+        let upper = &lower * 10;
+
+        // Start from an estimate of floor(log10|x|) and correct it until the
+        // scaled value has exactly the requested number of digits.
+        let mut k = ((self.top() - 1) as f64 * std::f64::consts::LOG10_2).floor() as i64;
+        let n = loop {
+            // Scaling goes through floating point rather than exact integers so
+            // that numbers with huge exponents are not expanded in full.
+            let scale = BigInt::from(digits as i64 - 1 - k);
+            let wp = (digits as f64 * std::f64::consts::LOG2_10) as u64 + scale.bits() + GUARD_BITS;
+            let factor = Float::from_int(10).powi(&scale, wp).expect("power of ten is representable");
+            let n = self.abs().mul(&factor, wp).round_to_int();
+
+            if n >= upper {
+                k += 1;
+            }
+            else if n < lower {
+                k -= 1;
+            }
+            else {
+                break n;
+            }
+        };
+
+        let s = n.to_string();
+        let sign = if self.is_negative() { "-" } else { "" };
+
+        match k {
+            k if k >= 0 && k < digits as i64 - 1 => {
+                let (int, frac) = s.split_at(k as usize + 1);
+                format!("{}{}.{}", sign, int, frac)
+            }
+            k if k == digits as i64 - 1 => format!("{}{}", sign, s),
+            k if (-5..0).contains(&k) => format!("{}0.{}{}", sign, "0".repeat((-k - 1) as usize), s),
+            k => {
+                let (int, frac) = s.split_at(1);
+                match frac.is_empty() {
+                    true => format!("{}{}e{}", sign, int, k),
+                    false => format!("{}{}.{}e{}", sign, int, frac, k),
+                }
+            }
+        }
+    }
+}
+
+impl Neg for Float {
+    type Output = Float;
+
+    fn neg(self) -> Self::Output {
+        Float { mant: -self.mant, exp: self.exp }
+    }
+}
+
+impl cmp::Ord for Float {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        let exp = cmp::min(self.exp, other.exp);
+        let a = &self.mant << (self.exp - exp) as u64;
+        let b = &other.mant << (other.exp - exp) as u64;
+        a.cmp(&b)
+    }
+}
+
+impl cmp::PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl cmp::Eq for Float {}
+
+impl cmp::PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,224 @@
+use num_bigint::BigInt;
+use num_integer::Integer as _;
+use num_traits::{One, Signed, Zero};
+use num_rational::BigRational;
+
+use crate::expression::{Expression, MathError};
+use crate::factor;
+use crate::polynomial::Polynomial;
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Sum, Product, Power};
+
+// Writes an expression as a single fraction over a common denominator.
+pub fn together(u: Expression) -> Result<Expression, MathError> {
+    let (n, d) = numer_denom(&u.simplify()?)?;
+    div!(n.expand()?, d).simplify()
+}
+
+// Writes an expression as a single fraction and divides the polynomial
+// greatest common divisor out of its numerator and denominator. The result
+// has integer coefficients and a denominator with a positive leading
+// coefficient. Fractions that are not rational functions are only brought
+// together.
+pub fn cancel(u: Expression) -> Result<Expression, MathError> {
+    let (n, d) = numer_denom(&u.simplify()?)?;
+    let (n, d) = (n.expand()?, d.expand()?);
+
+    let mut vars = Polynomial::variables(&n);
+    vars.extend(Polynomial::variables(&d));
+    vars.sort();
+    vars.dedup();
+
+    let (Some(p), Some(q)) = (Polynomial::from_expression(&n, &vars), Polynomial::from_expression(&d, &vars)) else {
+        return div!(n, d).simplify()
+    };
+
+    let g = p.gcd(&q);
+    let (p, q) = (p.exact_div(&g), q.exact_div(&g));
+
+    let den = p.coefficients()
+        .chain(q.coefficients())
+        .fold(BigInt::one(), |acc, c| acc.lcm(c.denom()));
+    let content = p.coefficients()
+        .chain(q.coefficients())
+        .fold(BigInt::zero(), |acc, c| acc.gcd(&(c * &den).to_integer()));
+
+    let mut scale = BigRational::new(den, content);
+    if q.leading_coefficient().is_negative() {
+        scale = -scale;
+    }
+
+    div!(p.scale(&scale).to_expression(), q.scale(&scale).to_expression()).simplify()
+}
+
+// A rational function written as a polynomial plus proper fractions
+// `c / f^j`, each stored as `(c, f, j)` with f irreducible over the
+// integers and c of lower degree than f.
+pub struct PartialFractions {
+    pub polynomial: Polynomial,
+    pub fractions: Vec<(Polynomial, Polynomial, u32)>,
+}
+
+// Decomposes a rational function of x into a polynomial plus proper
+// fractions whose denominators are powers of the irreducible factors of
+// the original denominator.
+pub fn apart(u: Expression, x: Expression) -> Result<Expression, MathError> {
+    let u = u.simplify()?;
+    let x = x.simplify()?;
+
+    let Some(decomposition) = partial_fractions(&u, &x)? else {
+        return Err(MathError::Undefined(format!("`{}` is not a rational function of `{}`", u, x)))
+    };
+
+    let mut terms = vec![decomposition.polynomial.to_expression()];
+    for (c, f, j) in decomposition.fractions {
+        terms.push(prod!(c.to_expression(), pow!(f.to_expression(), int!(-i64::from(j)))));
+    }
+
+    Sum::new(terms).simplify()
+}
+
+// Finds the partial fraction decomposition of a simplified expression, or
+// returns `None` if it is not a rational function of x.
+pub fn partial_fractions(u: &Expression, x: &Expression) -> Result<Option<PartialFractions>, MathError> {
+    let (n, d) = numer_denom(u)?;
+    let vars = vec![x.clone()];
+    let (Some(p), Some(q)) = (
+        Polynomial::from_expression(&n.expand()?, &vars),
+        Polynomial::from_expression(&d.expand()?, &vars),
+    ) else {
+        return Ok(None)
+    };
+
+    let g = p.gcd(&q);
+    let (p, q) = (p.exact_div(&g), q.exact_div(&g));
+    let (polynomial, r) = p.div_rem(&q)?;
+
+    let (unit, factors) = factor::factor_polynomial(&q);
+    let r = r.scale(&unit.recip());
+    let powers: Vec<Polynomial> = factors.iter().map(|(f, m)| f.pow(*m)).collect();
+
+    let mut fractions = Vec::new();
+    for (k, (f, m)) in factors.iter().enumerate() {
+        // The numerator over f^m is r times the inverse of the other
+        // factors modulo f^m, which is then written in powers of f.
+        let rest = powers.iter()
+            .enumerate()
+            .filter(|(j, _)| *j != k)
+            .fold(Polynomial::constant(vars.clone(), BigRational::one()), |acc, (_, h)| acc.mul(h));
+        let (s, _, _) = rest.gcdex(&powers[k])?;
+        let mut a = r.mul(&s).div_rem(&powers[k])?.1;
+
+        for j in (1..=*m).rev() {
+            let (next, c) = a.div_rem(f)?;
+            if !c.is_zero() {
+                fractions.push((c, f.clone(), j));
+            }
+            a = next;
+        }
+    }
+
+    Ok(Some(PartialFractions { polynomial, fractions }))
+}
+
+// Splits a simplified expression into a numerator and a denominator,
+// adding fractions in sums over the least common multiple of their
+// denominators when those are polynomials.
+pub fn numer_denom(u: &Expression) -> Result<(Expression, Expression), MathError> {
+    match u {
+        Expression::Rational(q) => Ok((int!(q.num().clone()), int!(q.den().clone()))),
+
+        Expression::Power(p) => match p.exp.as_ref() {
+            Expression::Integer(n) if n.num().is_negative()
+                => Ok((int!(1), pow!(*p.base.clone(), int!(-n.num())).simplify()?)),
+            Expression::Rational(q) if q.num().is_negative()
+                => Ok((int!(1), pow!(*p.base.clone(), frac!(-q.num(), q.den().clone())).simplify()?)),
+            _ => Ok((u.clone(), int!(1))),
+        },
+
+        Expression::Product(p) => {
+            let mut ns = Vec::new();
+            let mut ds = Vec::new();
+            for v in p.values() {
+                let (n, d) = numer_denom(v)?;
+                ns.push(n);
+                ds.push(d);
+            }
+            Ok((Product::new(ns).simplify()?, Product::new(ds).simplify()?))
+        }
+
+        Expression::Sum(s) => {
+            let mut n = int!(0);
+            let mut d = int!(1);
+            for v in s.values() {
+                let (vn, vd) = numer_denom(v)?;
+                if vd == d {
+                    n = sum!(n, vn).simplify()?;
+                }
+                else {
+                    let (a, b) = cofactors(&d, &vd);
+                    n = sum!(prod!(n, a.clone()), prod!(vn, b)).simplify()?;
+                    d = prod!(d, a).simplify()?;
+                }
+            }
+            Ok((n, d))
+        }
+
+        _ => Ok((u.clone(), int!(1))),
+    }
+}
+
+// Returns `(b/g, a/g)` where g is the polynomial greatest common divisor of
+// a and b, or `(b, a)` if either is not a polynomial.
+fn cofactors(a: &Expression, b: &Expression) -> (Expression, Expression) {
+    let mut vars = Polynomial::variables(a);
+    vars.extend(Polynomial::variables(b));
+    vars.sort();
+    vars.dedup();
+
+    match (Polynomial::from_expression(a, &vars), Polynomial::from_expression(b, &vars)) {
+        (Some(p), Some(q)) => {
+            let g = p.gcd(&q);
+            (q.exact_div(&g).to_expression(), p.exact_div(&g).to_expression())
+        }
+        _ => (b.clone(), a.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::types::Variable;
+
+    #[test]
+    fn together_expands_the_numerator() {
+        assert_eq!(together(parse("1/(x-1) - 1/(x+1)")).unwrap(), parse("2/((x-1)(x+1))"));
+        assert_eq!(together(parse("1/x + 1/y")).unwrap(), parse("(x + y)/(x y)"));
+    }
+
+    #[test]
+    fn cancel_divides_out_the_gcd() {
+        assert_eq!(cancel(parse("(x^2 - 1)/(x^2 + 2x + 1)")).unwrap(), parse("(x - 1)/(x + 1)"));
+        assert_eq!(cancel(parse("(2x + 2)/(4x^2 - 4)")).unwrap(), parse("1/(2x - 2)"));
+    }
+
+    #[test]
+    fn apart_splits_over_linear_factors() {
+        let x = var!("x");
+        assert_eq!(apart(parse("1/(x^2 - 1)"), x.clone()).unwrap(), parse("1/(2(x - 1)) - 1/(2(x + 1))"));
+        assert_eq!(apart(parse("(x^3 + 1)/(x^2 - x)"), x).unwrap(), parse("x + 1 - 1/x + 2/(x - 1)"));
+    }
+
+    #[test]
+    fn apart_handles_repeated_and_quadratic_factors() {
+        let x = var!("x");
+        assert_eq!(apart(parse("1/(x^2 (x + 1))"), x.clone()).unwrap(), parse("1/x^2 - 1/x + 1/(x + 1)"));
+        assert_eq!(apart(parse("1/(x*(x^2 + 1))"), x).unwrap(), parse("1/x - x/(x^2 + 1)"));
+    }
+
+    #[test]
+    fn apart_rejects_other_expressions() {
+        assert!(apart(parse("sin(x)/x"), var!("x")).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use num_traits::Signed;
+
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::fraction;
+use crate::limit;
+use crate::polynomial::Polynomial;
+use crate::solve::{self, Solutions};
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Sum, Product, Power, Function, Inequality, Relation};
+
+// Bits of precision used to put the critical points of an inequality in order.
+const ORDER_PRECISION: u64 = 128;
+
+// An interval of the real line, where a missing endpoint is infinite. A
+// closed interval whose endpoints agree is a single point.
+pub struct Interval {
+    start: Option<Expression>,
+    end: Option<Expression>,
+    closed_start: bool,
+    closed_end: bool,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(a), Some(b), true, true) = (&self.start, &self.end, self.closed_start, self.closed_end) {
+            if a == b {
+                return write!(f, "{{{}}}", a)
+            }
+        }
+
+        write!(f, "{}", if self.closed_start { "[" } else { "(" })?;
+        match &self.start {
+            Some(a) => write!(f, "{}, ", a)?,
+            None => write!(f, "-oo, ")?,
+        }
+        match &self.end {
+            Some(b) => write!(f, "{}", b)?,
+            None => write!(f, "oo")?,
+        }
+        write!(f, "{}", if self.closed_end { "]" } else { ")" })
+    }
+}
+
+// A root of one of the conditions being solved, along with the indices of
+// the conditions it is a zero of and whether it is a pole of any of them.
+struct CriticalPoint {
+    value: Float,
+    point: Expression,
+    zero_of: Vec<usize>,
+    pole: bool,
+}
+
+// Solves a polynomial or rational inequality in one unknown over the reals,
+// giving its solutions as a union of disjoint intervals in increasing order.
+pub fn solve_inequality(inequality: &Inequality, x: &Expression) -> Result<Vec<Interval>, MathError> {
+    let condition = Inequality::new(inequality.zero_form()?, inequality.relation, int!(0));
+    solve_conditions(&[condition], x)
+}
+
+// The real values of x for which u is defined, which excludes division by
+// zero, even roots of negative numbers, logarithms of non-positive ones and
+// the other points where a function has no real value.
+pub fn domain(u: &Expression, x: &Expression) -> Result<Vec<Interval>, MathError> {
+    solve_conditions(&conditions_on(u, x)?, x)
+}
+
+fn conditions_on(u: &Expression, x: &Expression) -> Result<Vec<Inequality>, MathError> {
+    let mut conditions = Vec::new();
+    collect_conditions(u, &mut conditions)?;
+    conditions.retain(|c| !c.lhs.free_of(x));
+    Ok(conditions)
+}
+
+// Whether u is defined at every point of some open interval that ends at the
+// given point when `below` is set, or that starts at it otherwise. The point
+// may be `oo` when approached from below or `-oo` when approached from above.
+pub fn defined_beside(u: &Expression, x: &Expression, point: &Expression, below: bool) -> Result<bool, MathError> {
+    let value = match limit::infinity_sign(point) {
+        Some(_) => None,
+        None => Some(point.evaluate(ORDER_PRECISION)?),
+    };
+
+    // Conditions that only exclude single points, such as the poles of
+    // `tan(x)`, leave u defined beside every point.
+    let mut conditions = conditions_on(u, x)?;
+    conditions.retain(|c| c.relation != Relation::NotEqual);
+
+    for interval in solve_conditions(&conditions, x)? {
+        let start = interval.start.map(|a| a.evaluate(ORDER_PRECISION)).transpose()?;
+        let end = interval.end.map(|b| b.evaluate(ORDER_PRECISION)).transpose()?;
+        let reaches = match below {
+            true => start.is_none_or(|a| value.as_ref().is_none_or(|p| a < *p))
+                && end.is_none_or(|b| value.as_ref().is_some_and(|p| b >= *p)),
+            false => end.is_none_or(|b| value.as_ref().is_none_or(|p| b > *p))
+                && start.is_none_or(|a| value.as_ref().is_some_and(|p| a <= *p)),
+        };
+        if reaches {
+            return Ok(true)
+        }
+    }
+    Ok(false)
+}
+
+// Gathers the conditions `v relation 0` under which every part of the
+// unsimplified expression u is defined, with the conditions on the parts of
+// an expression before its own. Functions whose real domain is unknown have
+// no conditions to give.
+fn collect_conditions(u: &Expression, conditions: &mut Vec<Inequality>) -> Result<(), MathError> {
+    match u {
+        Expression::Sum(s) => s.values().iter().try_for_each(|v| collect_conditions(v, conditions)),
+        Expression::Product(p) => p.values().iter().try_for_each(|v| collect_conditions(v, conditions)),
+        Expression::Power(p) => {
+            collect_conditions(&p.base, conditions)?;
+            collect_conditions(&p.exp, conditions)?;
+
+            let relation = match p.exp.clone().simplify()? {
+                Expression::Integer(n) if n.num().is_negative() => Relation::NotEqual,
+                Expression::Integer(_) => return Ok(()),
+                Expression::Rational(q) => match (q.num().is_negative(), !q.den().bit(0)) {
+                    (true, true) => Relation::Greater,
+                    (false, true) => Relation::GreaterEqual,
+                    (true, false) => Relation::NotEqual,
+                    (false, false) => return Ok(()),
+                },
+                // Powers with irrational exponents such as `x^pi` need a base
+                // that is not negative, and those with symbolic ones such as
+                // `x^x` a positive base.
+                e => match e.sign() {
+                    Some(Ordering::Greater) => Relation::GreaterEqual,
+                    _ => Relation::Greater,
+                },
+            };
+            conditions.push(Inequality::new(*p.base.clone(), relation, int!(0)));
+            Ok(())
+        }
+        Expression::Function(f) => {
+            for v in f.args() {
+                collect_conditions(v, conditions)?;
+            }
+            // The base of a logarithm is positive and other than 1.
+            if let Function::Log(b, _) = f {
+                conditions.push(Inequality::new(*b.clone(), Relation::Greater, int!(0)));
+                conditions.push(Inequality::new(sum!(*b.clone(), int!(-1)), Relation::NotEqual, int!(0)));
+            }
+            let v = f.arg().clone();
+            let (lhs, relation) = match f {
+                Function::Sqrt(_) => (v, Relation::GreaterEqual),
+                Function::Log(..) | Function::Ln(_) => (v, Relation::Greater),
+                Function::Root(_, n) => match n.clone().simplify()? {
+                    Expression::Integer(n) if n.num().bit(0) => return Ok(()),
+                    Expression::Integer(_) => (v, Relation::GreaterEqual),
+                    _ => (v, Relation::Greater),
+                },
+                // |v| <= 1, |v| < 1 and v >= 1.
+                Function::Asin(_) | Function::Acos(_) => (sum!(int!(1), neg!(pow!(v, int!(2)))), Relation::GreaterEqual),
+                Function::Atanh(_) => (sum!(int!(1), neg!(pow!(v, int!(2)))), Relation::Greater),
+                Function::Acosh(_) => (sum!(v, int!(-1)), Relation::GreaterEqual),
+                // The poles of the quotients of sin and cos.
+                Function::Tan(_) | Function::Sec(_) => (func!("cos"; v), Relation::NotEqual),
+                Function::Csc(_) | Function::Cot(_) => (func!("sin"; v), Relation::NotEqual),
+                Function::Arg(_) => (v, Relation::NotEqual),
+                Function::Cbrt(_) | Function::Abs(_) | Function::Exp(_) | Function::Re(_) | Function::Im(_) | Function::Conj(_)
+                | Function::Sin(_) | Function::Cos(_) | Function::Atan(_) | Function::Sinh(_) | Function::Cosh(_)
+                | Function::Tanh(_) | Function::Asinh(_) | Function::Max(_) | Function::Min(_) => return Ok(()),
+                Function::Gcd(_) | Function::Lcm(_) | Function::Order(_) | Function::Other(..) => {
+                    return Err(MathError::NoClosedForm(format!("the real domain of `{}`", u.clone().simplify()?)))
+                }
+            };
+            conditions.push(Inequality::new(lhs, relation, int!(0)));
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Solves conditions of the form `v relation 0` simultaneously by a sign
+// chart: the real zeros and poles of every v split the line into open
+// intervals on which no sign changes, so one sample point decides each.
+fn solve_conditions(conditions: &[Inequality], x: &Expression) -> Result<Vec<Interval>, MathError> {
+    let mut values = Vec::new();
+    let mut points = Vec::new();
+    for (k, c) in conditions.iter().enumerate() {
+        let v = c.lhs.clone().simplify()?;
+        let (n, d) = fraction::numer_denom(&v)?;
+        for (value, point) in zeros(n, x)? {
+            points.push(CriticalPoint { value, point, zero_of: vec![k], pole: false });
+        }
+        for (value, point) in zeros(d, x)? {
+            points.push(CriticalPoint { value, point, zero_of: Vec::new(), pole: true });
+        }
+        values.push(v);
+    }
+
+    points.sort_by(|a, b| a.value.cmp(&b.value));
+    points.dedup_by(|a, b| {
+        let same = a.value == b.value;
+        if same {
+            b.zero_of.append(&mut a.zero_of);
+            b.pole |= a.pole;
+        }
+        same
+    });
+
+    // Walks the open intervals and the points between them from left to
+    // right, merging neighbouring pieces of the solution as it goes.
+    let mut intervals: Vec<Interval> = Vec::new();
+    let mut extending = false;
+    for i in 0..=points.len() {
+        let start = i.checked_sub(1).map(|j| points[j].point.clone());
+        let end = points.get(i).map(|p| p.point.clone());
+        let sample = match (&start, &end) {
+            (Some(a), Some(b)) => div!(sum!(a.clone(), b.clone()), int!(2)),
+            (Some(a), None) => sum!(a.clone(), int!(1)),
+            (None, Some(b)) => sum!(b.clone(), int!(-1)),
+            (None, None) => int!(0),
+        }.simplify()?;
+
+        // Once a condition fails, the later ones may be undefined there.
+        let mut included = true;
+        for (c, v) in conditions.iter().zip(&values) {
+            if !included {
+                break;
+            }
+            included &= holds(c.relation, sign_at(v, x, &sample)?);
+        }
+        extend(&mut intervals, &mut extending, included, start, end.clone(), false);
+
+        let Some(p) = points.get(i) else { break };
+        let mut included = !p.pole;
+        for (k, (c, v)) in conditions.iter().zip(&values).enumerate() {
+            if !included {
+                break;
+            }
+            let sign = match p.zero_of.contains(&k) {
+                true => Ordering::Equal,
+                false => sign_at(v, x, &p.point)?,
+            };
+            included &= holds(c.relation, sign);
+        }
+        extend(&mut intervals, &mut extending, included, end.clone(), end, true);
+    }
+
+    Ok(intervals)
+}
+
+// Adds a piece of the solution, continuing the last interval when the
+// piece before it was also part of the solution.
+fn extend(intervals: &mut Vec<Interval>, extending: &mut bool, included: bool, start: Option<Expression>, end: Option<Expression>, closed: bool) {
+    match (included, intervals.last_mut()) {
+        (false, _) => (),
+        (true, Some(last)) if *extending => {
+            last.end = end;
+            last.closed_end = closed;
+        }
+        (true, _) => intervals.push(Interval { start, end, closed_start: closed, closed_end: closed }),
+    }
+    *extending = included;
+}
+
+// The real points where u may vanish, which are the real roots of a
+// polynomial or rational u and are otherwise found through the functions u
+// is made of. Extra points do no harm, as the sign chart only gets finer.
+fn zeros(u: Expression, x: &Expression) -> Result<Vec<(Float, Expression)>, MathError> {
+    let shifted = |v: &Expression| zeros(sum!(v.clone(), int!(-1)).simplify()?, x);
+    match &u {
+        Expression::Product(p) => {
+            let mut points = Vec::new();
+            for v in p.values() {
+                points.extend(zeros(v.clone(), x)?);
+            }
+            Ok(points)
+        }
+        Expression::Power(p) if Polynomial::rational(&p.exp).is_some() || !p.base.free_of(x) => zeros(*p.base.clone(), x),
+        Expression::Function(f) => match f {
+            Function::Exp(_) | Function::Cosh(_) => Ok(Vec::new()),
+            Function::Ln(v) | Function::Log(_, v) | Function::Acos(v) | Function::Acosh(v) => shifted(v),
+            Function::Sqrt(v) | Function::Cbrt(v) | Function::Root(v, _) | Function::Abs(v) | Function::Asin(v)
+            | Function::Atan(v) | Function::Sinh(v) | Function::Tanh(v) | Function::Asinh(v) | Function::Atanh(v)
+                => zeros(*v.clone(), x),
+            Function::Sin(_) | Function::Cos(_) => Err(MathError::NoClosedForm(format!("the infinitely many zeros of `{}`", u))),
+            _ => Err(MathError::NoClosedForm(format!("the zeros of `{}`", u))),
+        },
+        _ => real_roots(u, x),
+    }
+}
+
+// The real roots of u in x along with their numerical values. Complex roots
+// are dropped, while roots depending on other variables cannot be ordered.
+pub fn real_roots(u: Expression, x: &Expression) -> Result<Vec<(Float, Expression)>, MathError> {
+    let Solutions::Finite(roots) = solve::solve(u, x)? else {
+        return Ok(Vec::new())
+    };
+
+    let mut real = Vec::new();
+    for root in roots {
+        match root.evaluate(ORDER_PRECISION) {
+            Ok(value) => real.push((value, root)),
+            Err(_) if Polynomial::variables(&root).is_empty() => (),
+            Err(_) => return Err(MathError::Undefined(format!("Cannot decide the order of the root `{}`", root))),
+        }
+    }
+    Ok(real)
+}
+
+fn sign_at(v: &Expression, x: &Expression, point: &Expression) -> Result<Ordering, MathError> {
+    let v = v.substitute(x, point).simplify()?;
+    v.sign().ok_or_else(|| MathError::Undefined(format!("Cannot decide the sign of `{}`", v)))
+}
+
+fn holds(relation: Relation, sign: Ordering) -> bool {
+    match relation {
+        Relation::Less => sign == Ordering::Less,
+        Relation::LessEqual => sign != Ordering::Greater,
+        Relation::Greater => sign == Ordering::Greater,
+        Relation::GreaterEqual => sign != Ordering::Less,
+        Relation::NotEqual => sign != Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, parse_unsimplified};
+    use crate::types::Variable;
+
+    fn union(intervals: &[Interval]) -> String {
+        intervals.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" U ")
+    }
+
+    fn solved(text: &str) -> Result<String, MathError> {
+        let Expression::Inequality(i) = parse(text) else { panic!("`{}` is not an inequality", text) };
+        solve_inequality(&i, &var!("x")).map(|intervals| union(&intervals))
+    }
+
+    fn domain_of(text: &str) -> Result<String, MathError> {
+        domain(&parse_unsimplified(text), &var!("x")).map(|intervals| union(&intervals))
+    }
+
+    #[test]
+    fn polynomial_and_rational_inequalities() {
+        assert_eq!(solved("x^2 - 1 > 0").unwrap(), "(-oo, -1) U (1, oo)");
+        assert_eq!(solved("(x - 1)/(x + 2) <= 0").unwrap(), "(-2, 1]");
+        assert_eq!(solved("x^2 >= 0").unwrap(), "(-oo, oo)");
+        assert_eq!(solved("x^2 + 1 < 0").unwrap(), "");
+        assert_eq!(solved("x^2 <= 0").unwrap(), "{0}");
+    }
+
+    #[test]
+    fn domains_of_quotients_roots_and_logarithms() {
+        assert_eq!(domain_of("1/(x^2 - 4)").unwrap(), "(-oo, -2) U (-2, 2) U (2, oo)");
+        assert_eq!(domain_of("sqrt(x - 1) + ln(3 - x)").unwrap(), "[1, 3)");
+        assert_eq!(domain_of("x/x").unwrap(), "(-oo, 0) U (0, oo)");
+        assert_eq!(domain_of("log(x, 2)").unwrap(), "(0, 1) U (1, oo)");
+    }
+
+    #[test]
+    fn domains_of_inverse_functions() {
+        assert_eq!(domain_of("asin(x)").unwrap(), "[-1, 1]");
+        assert_eq!(domain_of("acos(2x - 1)").unwrap(), "[0, 1]");
+        assert_eq!(domain_of("asin(1/x)").unwrap(), "(-oo, -1] U [1, oo)");
+        assert_eq!(domain_of("acosh(x)").unwrap(), "[1, oo)");
+        assert_eq!(domain_of("atanh(x)").unwrap(), "(-1, 1)");
+    }
+
+    #[test]
+    fn domains_of_real_powers() {
+        assert_eq!(domain_of("x^x").unwrap(), "(0, oo)");
+        assert_eq!(domain_of("x^pi").unwrap(), "[0, oo)");
+        assert_eq!(domain_of("root(x, 4)").unwrap(), "[0, oo)");
+        assert_eq!(domain_of("root(x, 3)").unwrap(), "(-oo, oo)");
+    }
+
+    #[test]
+    fn conditions_that_are_not_polynomial() {
+        assert_eq!(domain_of("1/sqrt(x)").unwrap(), "(0, oo)");
+        assert_eq!(domain_of("sqrt(x - 1)/ln(x)").unwrap(), "(1, oo)");
+        assert_eq!(domain_of("ln(ln(x))").unwrap(), "(1, oo)");
+        assert_eq!(domain_of("exp(1/x)").unwrap(), "(-oo, 0) U (0, oo)");
+    }
+
+    #[test]
+    fn unknown_domains_fail_closed() {
+        assert!(matches!(domain_of("floor(x)"), Err(MathError::NoClosedForm(_))));
+        assert!(matches!(domain_of("tan(x)"), Err(MathError::NoClosedForm(_))));
+    }
+
+    #[test]
+    fn defined_beside_a_point() {
+        let beside = |text: &str, point: &str, below: bool| {
+            defined_beside(&parse_unsimplified(text), &var!("x"), &parse(point), below).unwrap()
+        };
+        assert!(beside("sqrt(x)", "0", false));
+        assert!(!beside("sqrt(x)", "0", true));
+        assert!(beside("1/x", "0", true));
+        assert!(!beside("sqrt(-x)", "oo", true));
+        assert!(!beside("x^x", "0", true));
+        assert!(!beside("asin(x)", "1", false));
+        assert!(beside("tan(x)", "pi/2", true));
+    }
+}
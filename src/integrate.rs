@@ -0,0 +1,482 @@
+use std::cmp::Ordering;
+
+use num_rational::BigRational;
+use num_traits::Signed;
+
+use crate::expression::{Expression, MathError};
+use crate::fraction;
+use crate::inequality;
+use crate::limit::{self, Direction};
+use crate::polynomial::Polynomial;
+use crate::settings;
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power, Function};
+
+// How deeply substitution and integration by parts may nest.
+const MAX_DEPTH: u32 = 8;
+
+// The variable introduced when integrating by substitution. The lexer only
+// produces names made of letters, so it never clashes with a symbol in the input.
+const SUBSTITUTION_VARIABLE: &str = "_u";
+
+// Finds an antiderivative of u with respect to x, without a constant of
+// integration.
+pub fn integrate(u: Expression, x: &Expression) -> Result<Expression, MathError> {
+    let u = u.simplify()?;
+    match antiderivative(&u, x, MAX_DEPTH)? {
+        Some(f) => Ok(f),
+        None => Err(MathError::NoClosedForm(format!("the integral of `{}` with respect to `{}`", u, x))),
+    }
+}
+
+// Evaluates the definite integral of u from a to b through an antiderivative.
+// The interval is split at the real poles of u inside it, and the pieces are
+// evaluated with one-sided limits of the antiderivative wherever it cannot be
+// substituted, so that an improper integral either converges or is reported
+// to diverge.
+pub fn integrate_between(u: Expression, x: &Expression, a: Expression, b: Expression) -> Result<Expression, MathError> {
+    let u = u.simplify()?;
+    let (a, b) = (a.simplify()?, b.simplify()?);
+    if compare(&a, &b) == Some(Ordering::Greater) {
+        return neg!(integrate_between(u, x, b, a)?).simplify()
+    }
+
+    let mut f = integrate(u.clone(), x)?;
+    if !settings::complex_mode() {
+        f = absolute_logs(&f, &u)?;
+    }
+    let diverges = || MathError::Undefined(format!("The integral of `{}` from `{}` to `{}` diverges", u, a, b));
+
+    let mut points = vec![(a.clone(), false)];
+    for p in poles(&u, x)? {
+        match (compare(&p, &a), compare(&p, &b)) {
+            (Some(Ordering::Greater), Some(Ordering::Less)) => points.push((p, true)),
+            (Some(Ordering::Less), Some(Ordering::Less))
+            | (Some(Ordering::Greater), Some(Ordering::Greater))
+            | (Some(Ordering::Equal), _)
+            | (_, Some(Ordering::Equal)) => (),
+            _ => return Err(MathError::Undefined(format!(
+                "Cannot decide whether the pole `{}` lies between `{}` and `{}`", p, a, b
+            ))),
+        }
+    }
+    points.push((b.clone(), false));
+
+    // A divergent piece is reported even when the antiderivative cannot be
+    // evaluated at the ends of another.
+    let mut terms = Vec::new();
+    let mut failure = None;
+    for pair in points.windows(2) {
+        let ends = [(&pair[1], Direction::Left, false), (&pair[0], Direction::Right, true)];
+        for ((point, pole), direction, lower) in ends {
+            match edge(&f, x, point, *pole, direction) {
+                Ok(v) if !v.free_of(&var!(limit::INFINITY)) => return Err(diverges()),
+                Ok(v) if lower => terms.push(neg!(v)),
+                Ok(v) => terms.push(v),
+                Err(e) => failure = failure.or(Some(e)),
+            }
+        }
+    }
+
+    if let Some(e) = failure {
+        return Err(e)
+    }
+
+    // Values at numeric bounds are expanded, so that logarithms split up at
+    // different ends collect into one.
+    let value = Sum::new(terms).simplify()?;
+    match Polynomial::variables(&value).is_empty() {
+        true => value.expand(),
+        false => Ok(value),
+    }
+}
+
+// Writes the logarithms that an antiderivative brought in, rather than took
+// over from the integrand, as `ln|v|`, which has the same derivative and is
+// also defined where v is negative.
+fn absolute_logs(f: &Expression, u: &Expression) -> Result<Expression, MathError> {
+    match f {
+        Expression::Sum(s) => Sum::new(s.values().iter().map(|v| absolute_logs(v, u)).collect::<Result<_, _>>()?).simplify(),
+        Expression::Product(p) => Product::new(p.values().iter().map(|v| absolute_logs(v, u)).collect::<Result<_, _>>()?).simplify(),
+        Expression::Power(p) => pow!(absolute_logs(&p.base, u)?, absolute_logs(&p.exp, u)?).simplify(),
+        Expression::Function(Function::Ln(v)) if u.free_of(f) => ln!(func!("abs"; *v.clone())).simplify(),
+        Expression::Function(g) => Expression::Function(g.map(|v| absolute_logs(v, u))?).simplify(),
+        f => Ok(f.clone()),
+    }
+}
+
+// The value of an antiderivative at an end of a piece of the interval, found
+// by substitution unless the point is infinite or a pole of the integrand.
+fn edge(f: &Expression, x: &Expression, point: &Expression, pole: bool, direction: Direction) -> Result<Expression, MathError> {
+    if !pole && limit::infinity_sign(point).is_none() {
+        if let Ok(v) = f.substitute(x, point).simplify() {
+            return Ok(v)
+        }
+    }
+    limit::limit(f.clone(), x, point.clone(), direction)
+}
+
+// The real zeros of the denominator of u, in increasing order. Denominators
+// that are not polynomials are not searched, so that their poles are only
+// caught at the ends of the interval.
+fn poles(u: &Expression, x: &Expression) -> Result<Vec<Expression>, MathError> {
+    let (_, d) = fraction::numer_denom(u)?;
+    let d = d.expand()?;
+    if d.free_of(x) || Polynomial::from_expression(&d, &Polynomial::variables(&d)).is_none() {
+        return Ok(Vec::new())
+    }
+
+    let mut roots = inequality::real_roots(d, x)?;
+    roots.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(roots.into_iter().map(|(_, root)| root).collect())
+}
+
+// The order of two points of the real line, either of which may be infinite,
+// or `None` when it cannot be decided from the assumptions on their symbols.
+fn compare(u: &Expression, v: &Expression) -> Option<Ordering> {
+    if u == v {
+        return Some(Ordering::Equal)
+    }
+    match (limit::infinity_sign(u), limit::infinity_sign(v)) {
+        (Some(s), Some(t)) => Some(s.cmp(&t)),
+        (Some(s), None) => Some(s),
+        (None, Some(t)) => Some(t.reverse()),
+        (None, None) => {
+            let d = sum!(u.clone(), neg!(v.clone())).simplify().ok()?;
+            match d {
+                d if d == int!(0) => Some(Ordering::Equal),
+                d if d.is_positive() => Some(Ordering::Greater),
+                d if neg!(d.clone()).simplify().ok()?.is_positive() => Some(Ordering::Less),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn antiderivative(u: &Expression, x: &Expression, depth: u32) -> Result<Option<Expression>, MathError> {
+    if u.free_of(x) {
+        return prod!(u.clone(), x.clone()).simplify().map(Some)
+    }
+
+    match u {
+        Expression::Sum(s) => {
+            let mut terms = Vec::new();
+            for v in s.values() {
+                match antiderivative(v, x, depth)? {
+                    Some(f) => terms.push(f),
+                    None => return rational(u, x),
+                }
+            }
+            return Sum::new(terms).simplify().map(Some)
+        }
+
+        // Constant factors move outside the integral.
+        Expression::Product(p) => {
+            let (constant, variable): (Vec<_>, Vec<_>) = p.values().iter().cloned().partition(|v| v.free_of(x));
+            if !constant.is_empty() {
+                let Some(f) = antiderivative(&Product::new(variable).simplify()?, x, depth)? else {
+                    return Ok(None)
+                };
+                return prod!(Product::new(constant).into(), f).simplify().map(Some)
+            }
+        }
+
+        _ => (),
+    }
+
+    if let Some(f) = table(u, x)? {
+        return Ok(Some(f))
+    }
+    if depth == 0 {
+        return Ok(None)
+    }
+    if let Some(f) = substitution(u, x, depth)? {
+        return Ok(Some(f))
+    }
+    if let Some(f) = rational(u, x)? {
+        return Ok(Some(f))
+    }
+    if let Some(f) = parts(u, x, depth)? {
+        return Ok(Some(f))
+    }
+
+    match u.clone().expand()? {
+        v if &v != u => antiderivative(&v, x, depth - 1),
+        _ => Ok(None),
+    }
+}
+
+// The slope of an expression that is linear in x, or `None` otherwise.
+fn linear(v: &Expression, x: &Expression) -> Option<Expression> {
+    let a = v.derivative(x).ok()?;
+    (a.free_of(x) && a != int!(0)).then_some(a)
+}
+
+// Integrals of x, powers, exponentials, roots and logarithms, each of a
+// linear argument `a*x + b`.
+fn table(u: &Expression, x: &Expression) -> Result<Option<Expression>, MathError> {
+    if u == x {
+        return prod!(frac!(1, 2), pow!(x.clone(), int!(2))).simplify().map(Some)
+    }
+
+    let f = match u {
+        Expression::Power(p) => {
+            let (base, exp) = (p.base.as_ref(), p.exp.as_ref());
+            match (linear(base, x), linear(exp, x)) {
+                (Some(a), _) if exp.free_of(x) => match exp {
+                    Expression::Integer(n) if n.num() == &(-1).into() => prod!(ln!(base.clone()), inv!(a)),
+                    _ => {
+                        let n = sum!(exp.clone(), int!(1));
+                        prod!(pow!(base.clone(), n.clone()), inv!(prod!(n, a)))
+                    }
+                },
+                (_, Some(a)) if base.free_of(x) => prod!(u.clone(), inv!(prod!(ln!(base.clone()), a))),
+                _ => return Ok(None),
+            }
+        }
+
+        Expression::Function(function) => {
+            let v = function.arg().clone();
+            let Some(a) = linear(&v, x) else { return Ok(None) };
+            match function {
+                Function::Sqrt(_) => prod!(frac!(2, 3), pow!(v, frac!(3, 2)), inv!(a)),
+                Function::Cbrt(_) => prod!(frac!(3, 4), pow!(v, frac!(4, 3)), inv!(a)),
+                Function::Ln(_) => prod!(sum!(prod!(v.clone(), ln!(v.clone())), neg!(v)), inv!(a)),
+                Function::Log(b, _) if b.free_of(x) => prod!(
+                    sum!(prod!(v.clone(), ln!(v.clone())), neg!(v)),
+                    inv!(prod!(a, ln!(*b.clone()))),
+                ),
+                Function::Exp(_) => prod!(func!("exp"; v), inv!(a)),
+                Function::Sin(_) => prod!(int!(-1), func!("cos"; v), inv!(a)),
+                Function::Cos(_) => prod!(func!("sin"; v), inv!(a)),
+                Function::Sinh(_) => prod!(func!("cosh"; v), inv!(a)),
+                Function::Cosh(_) => prod!(func!("sinh"; v), inv!(a)),
+                _ => return Ok(None),
+            }
+        }
+
+        _ => return Ok(None),
+    };
+
+    f.simplify().map(Some)
+}
+
+// Tries writing u as f(g(x)) * g'(x) for some g appearing in u, and
+// integrates f instead.
+fn substitution(u: &Expression, x: &Expression, depth: u32) -> Result<Option<Expression>, MathError> {
+    let t = var!(SUBSTITUTION_VARIABLE);
+    let mut candidates = Vec::new();
+    collect_candidates(u, x, &mut candidates);
+
+    for g in candidates {
+        let Ok(dg) = g.derivative(x) else { continue };
+        let Ok(w) = prod!(u.clone(), inv!(dg)).simplify() else { continue };
+
+        let w = w.substitute(&g, &t);
+        if !w.free_of(x) {
+            continue;
+        }
+
+        if let Some(f) = antiderivative(&w.simplify()?, &t, depth - 1)? {
+            return f.substitute(&t, &g).simplify().map(Some)
+        }
+    }
+
+    Ok(None)
+}
+
+// Collects the function applications, arguments, bases and exponents
+// inside u that depend on x.
+fn collect_candidates(u: &Expression, x: &Expression, candidates: &mut Vec<Expression>) {
+    let mut push = |v: &Expression| {
+        if v != x && !v.free_of(x) && !candidates.contains(v) {
+            candidates.push(v.clone());
+        }
+    };
+
+    match u {
+        Expression::Sum(s) => s.values().iter().for_each(|v| collect_candidates(v, x, candidates)),
+        Expression::Product(p) => p.values().iter().for_each(|v| collect_candidates(v, x, candidates)),
+        Expression::Power(p) => {
+            push(&p.base);
+            push(&p.exp);
+            collect_candidates(&p.base, x, candidates);
+            collect_candidates(&p.exp, x, candidates);
+        }
+        Expression::Function(f) => {
+            push(u);
+            f.args().into_iter().for_each(&mut push);
+            f.args().into_iter().for_each(|v| collect_candidates(v, x, candidates));
+        }
+        _ => (),
+    }
+}
+
+// Integrates a rational function of x through its partial fractions, as
+// long as every irreducible factor of the denominator is linear or quadratic.
+fn rational(u: &Expression, x: &Expression) -> Result<Option<Expression>, MathError> {
+    let Some(decomposition) = fraction::partial_fractions(u, x)? else {
+        return Ok(None)
+    };
+
+    let p = &decomposition.polynomial;
+    let integral = (0..=p.degree(0)).fold(Polynomial::zero(vec![x.clone()]), |acc, k| {
+        acc.with_term(vec![k + 1], p.coefficient(&[k]) / BigRational::from_integer((k + 1).into()))
+    });
+
+    let mut terms = vec![integral.to_expression()];
+    for (c, f, j) in &decomposition.fractions {
+        let term = match f.degree(0) {
+            1 => {
+                let a = Polynomial::number(&f.coefficient(&[1]));
+                let power = match j {
+                    1 => ln!(f.to_expression()),
+                    j => {
+                        let n = 1 - i64::from(*j);
+                        prod!(pow!(f.to_expression(), int!(n)), inv!(int!(n)))
+                    }
+                };
+                prod!(c.to_expression(), power, inv!(a))
+            }
+            2 => quadratic_fraction(c, f, *j, x),
+            _ => return Ok(None),
+        };
+        terms.push(term);
+    }
+
+    Sum::new(terms).simplify().map(Some)
+}
+
+// The integral of (p*x + q) / f^j for an irreducible quadratic f = a*x^2 + b*x + c,
+// written as a multiple of f'/f^j plus a multiple of 1/f^j.
+fn quadratic_fraction(numerator: &Polynomial, f: &Polynomial, j: u32, x: &Expression) -> Expression {
+    let (a, b) = (f.coefficient(&[2]), f.coefficient(&[1]));
+    let (p, q) = (numerator.coefficient(&[1]), numerator.coefficient(&[0]));
+
+    let two_a = &a * BigRational::from_integer(2.into());
+    let log_part = match j {
+        1 => ln!(f.to_expression()),
+        j => {
+            let n = 1 - i64::from(j);
+            prod!(pow!(f.to_expression(), int!(n)), inv!(int!(n)))
+        }
+    };
+
+    sum!(
+        prod!(Polynomial::number(&(&p / &two_a)), log_part),
+        prod!(Polynomial::number(&(q - p * b / two_a)), reciprocal_quadratic(f, j, x)),
+    )
+}
+
+// The integral of 1/f^j for an irreducible quadratic f = a*x^2 + b*x + c with
+// discriminant d = 4ac - b^2, lowering j one step at a time.
+fn reciprocal_quadratic(f: &Polynomial, j: u32, x: &Expression) -> Expression {
+    let (a, b, c) = (f.coefficient(&[2]), f.coefficient(&[1]), f.coefficient(&[0]));
+    let four = BigRational::from_integer(4.into());
+    let d = &four * &a * &c - &b * &b;
+
+    // The derivative 2ax + b.
+    let df = sum!(prod!(Polynomial::number(&(&a + &a)), x.clone()), Polynomial::number(&b));
+    let fe = f.to_expression();
+
+    if j == 1 {
+        return match d.is_positive() {
+            true => {
+                let root = sqrt!(Polynomial::number(&d));
+                prod!(int!(2), inv!(root.clone()), func!("atan"; prod!(df, inv!(root))))
+            }
+            false => {
+                let root = sqrt!(Polynomial::number(&-d));
+                prod!(
+                    inv!(root.clone()),
+                    ln!(prod!(sum!(df.clone(), neg!(root.clone())), inv!(sum!(df, root)))),
+                )
+            }
+        }
+    }
+
+    let k = BigRational::from_integer((j - 1).into());
+    let scale = BigRational::from_integer((2 * (2 * i64::from(j) - 3)).into()) * &a / (&k * &d);
+    sum!(
+        prod!(df, inv!(prod!(Polynomial::number(&(k * &d)), pow!(fe, int!(j - 1))))),
+        prod!(Polynomial::number(&scale), reciprocal_quadratic(f, j - 1, x)),
+    )
+}
+
+// Integration by parts for a polynomial times one other factor. Logarithms
+// are differentiated away, while anything else is integrated as the
+// polynomial is differentiated down to a constant.
+fn parts(u: &Expression, x: &Expression, depth: u32) -> Result<Option<Expression>, MathError> {
+    let Expression::Product(p) = u else { return Ok(None) };
+
+    let vars = vec![x.clone()];
+    let (polynomial, rest): (Vec<_>, Vec<_>) = p.values()
+        .iter()
+        .cloned()
+        .partition(|v| Polynomial::from_expression(v, &vars).is_some());
+
+    let ([e], false) = (rest.as_slice(), polynomial.is_empty()) else {
+        return Ok(None)
+    };
+    let poly = Product::new(polynomial).simplify()?;
+
+    let result = match e {
+        Expression::Function(Function::Ln(_) | Function::Log(..)) => {
+            let Some(q) = rational(&poly, x)? else { return Ok(None) };
+            let rest = prod!(q.clone(), e.derivative(x)?).simplify()?;
+            let Some(w) = antiderivative(&rest, x, depth - 1)? else { return Ok(None) };
+            sum!(prod!(q, e.clone()), neg!(w))
+        }
+        _ => {
+            let Some(v) = antiderivative(e, x, depth - 1)? else { return Ok(None) };
+            let rest = prod!(poly.derivative(x)?, v.clone()).simplify()?;
+            let Some(w) = antiderivative(&rest, x, depth - 1)? else { return Ok(None) };
+            sum!(prod!(poly, v), neg!(w))
+        }
+    };
+
+    result.simplify()?.expand().map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn between(u: &str, a: &str, b: &str) -> Result<Expression, MathError> {
+        integrate_between(parse(u), &var!("x"), parse(a), parse(b))
+    }
+
+    #[test]
+    fn definite_integrals_through_an_antiderivative() {
+        assert_eq!(between("x^2", "-1", "1").unwrap(), parse("2/3"));
+        assert_eq!(between("x^2", "3", "1").unwrap(), parse("-26/3"));
+        assert_eq!(between("1/x", "1", "2").unwrap(), parse("ln(2)"));
+        assert_eq!(between("sqrt(x)", "0", "4").unwrap(), parse("16/3"));
+    }
+
+    #[test]
+    fn logarithms_hold_where_their_argument_is_negative() {
+        assert_eq!(between("1/x", "-2", "-1").unwrap(), parse("-ln(2)"));
+        assert_eq!(between("1/(x - 2)", "0", "1").unwrap(), parse("-ln(2)"));
+        assert_eq!(between("1/(x^2 - 1)", "-1/2", "1/2").unwrap(), parse("-ln(3)"));
+        assert!(between("ln(x)", "-2", "-1").is_err());
+    }
+
+    #[test]
+    fn improper_integrals_that_converge() {
+        assert_eq!(between("ln(x)", "0", "1").unwrap(), int!(-1));
+        assert_eq!(between("1/sqrt(x)", "0", "1").unwrap(), int!(2));
+        assert_eq!(between("1/x^2", "1", "oo").unwrap(), int!(1));
+        assert_eq!(between("1/(x^2 + 1)", "-oo", "oo").unwrap(), parse("pi"));
+    }
+
+    #[test]
+    fn poles_inside_the_interval_diverge() {
+        assert!(between("1/x^2", "-1", "1").is_err());
+        assert!(between("1/(x - 2)", "0", "3").is_err());
+        assert!(between("1/x", "-1", "1").is_err());
+        assert!(between("1/x^2", "0", "1").is_err());
+        assert!(between("x", "0", "oo").is_err());
+    }
+}
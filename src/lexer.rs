@@ -1,4 +1,5 @@
 use crate::tokens::Token;
+use num_bigint::BigInt;
 use core::fmt;
 use std::vec::IntoIter;
 use std::error::Error;
@@ -42,9 +43,9 @@ impl Lexer {
             }
 
             let token = match ch {
-                'a'..='z'       => self.generate_identifier(),
-                '.' | '0'..='9' => self.generate_number()?,
-                _               => self.generate_math_token()?,
+                'a'..='z' | 'A'..='Z' => self.generate_identifier(),
+                '.' | '0'..='9'       => self.generate_number()?,
+                _                     => self.generate_math_token()?,
             };
 
             tokens.push(token);
@@ -62,7 +63,7 @@ impl Lexer {
         self.advance();
 
         // Keep looping until no more characters are found.
-        while let Some(ch @ 'a'..='z') = self.current_char {
+        while let Some(ch @ ('a'..='z' | 'A'..='Z')) = self.current_char {
             identifier.push(ch);
             self.advance();
         }
@@ -70,21 +71,81 @@ impl Lexer {
         Token::Identifier(identifier)
     }
 
-    fn generate_number(&mut self) -> Result<Token, LexError> {
-        let mut number_str = self.current_char.unwrap().to_string();
-        self.advance();
+    fn peek(&self, n: usize) -> Option<char> {
+        self.chars.as_slice().get(n).copied()
+    }
 
-        // Keep looping until no more periods or digits are found.
-        while let Some(ch @ '.' | ch @ '0'..='9') = self.current_char {
+    fn take_digits(&mut self, number_str: &mut String) {
+        while let Some(ch @ '0'..='9') = self.current_char {
             number_str.push(ch);
             self.advance();
         }
+    }
+
+    fn generate_number(&mut self) -> Result<Token, LexError> {
+        let mut number_str = String::new();
+        self.take_digits(&mut number_str);
+
+        let mut is_decimal = false;
+
+        if let Some('.') = self.current_char {
+            is_decimal = true;
+            number_str.push('.');
+            self.advance();
+            self.take_digits(&mut number_str);
+
+            // A parenthesised block of digits straight after the fraction,
+            // as in `0.(3)`, marks the digits that repeat forever.
+            if let Some('(') = self.current_char {
+                let repeat_len = self.chars.as_slice()
+                    .iter()
+                    .take_while(|ch| ch.is_ascii_digit())
+                    .count();
+
+                if repeat_len > 0 && self.peek(repeat_len) == Some(')') {
+                    number_str.push('(');
+                    self.advance();
+                    self.take_digits(&mut number_str);
+                    number_str.push(')');
+                    self.advance();
+                }
+            }
+        }
+
+        // Scientific notation only applies when the `e` or `E` is followed by
+        // an exponent, so that input like `2e` is still lexed as `2` and `e`.
+        if let Some('e' | 'E') = self.current_char {
+            let has_exponent = match self.peek(0) {
+                Some('0'..='9') => true,
+                Some('+' | '-') => matches!(self.peek(1), Some('0'..='9')),
+                _ => false,
+            };
+
+            if has_exponent {
+                is_decimal = true;
+                number_str.push('e');
+                self.advance();
+                if let Some(sign @ ('+' | '-')) = self.current_char {
+                    number_str.push(sign);
+                    self.advance();
+                }
+                self.take_digits(&mut number_str);
+            }
+        }
+
+        if let Some('.') = self.current_char {
+            number_str.push('.');
+            return Err(LexError(format!("Invalid number syntax `{}`", number_str)))
+        }
 
-        number_str
-            .parse::<i32>()
-            .map(Token::Integer)
-            .or_else(|_| number_str.parse::<f32>().map(Token::Decimal))
-            .map_err(|_| LexError(format!("Invalid number syntax `{}`", number_str)))
+        match is_decimal {
+            true if number_str == "." => Err(LexError("Invalid number syntax `.`".to_string())),
+            true => Ok(Token::Decimal(number_str)),
+            false => number_str
+                .parse::<BigInt>()
+                .map(Token::Integer)
+                .map_err(|_| LexError(format!("Invalid number syntax `{}`", number_str))),
+        }
     }
 
     fn generate_math_token(&mut self) -> Result<Token, LexError> {
@@ -99,6 +160,20 @@ impl Lexer {
             ')' => Token::RightParen,
             '[' => Token::LeftBrack,
             ']' => Token::RightBrack,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            ',' => Token::Comma,
+            '=' => Token::Equal,
+            // Relations which may be followed by `=`.
+            '<' => return Ok(self.generate_relation(Token::Less, Token::LessEqual)),
+            '>' => return Ok(self.generate_relation(Token::Greater, Token::GreaterEqual)),
+            '!' => {
+                self.advance();
+                match self.current_char {
+                    Some('=') => Token::NotEqual,
+                    _ => return Err(LexError("Expected `=` after `!`".to_string())),
+                }
+            }
             // Invalid character.
             ch => return Err(LexError(format!("Invalid character `{}`", ch)))
         };
@@ -106,4 +181,49 @@ impl Lexer {
         self.advance();
         Ok(token)
     }
+
+    fn generate_relation(&mut self, strict: Token, non_strict: Token) -> Token {
+        self.advance();
+        match self.current_char {
+            Some('=') => {
+                self.advance();
+                non_strict
+            }
+            _ => strict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Expression;
+    use crate::parser::parse;
+    use crate::types::Rational;
+
+    fn lex(text: &str) -> Vec<Token> {
+        Lexer::new(text).tokens().unwrap()
+    }
+
+    #[test]
+    fn decimals_are_exact() {
+        assert_eq!(parse("0.1"), frac!(1, 10));
+        assert_eq!(parse("0.1 + 0.2"), frac!(3, 10));
+        assert_eq!(parse("0.(3)"), frac!(1, 3));
+        assert_eq!(parse("1.2(34)"), frac!(611, 495));
+    }
+
+    #[test]
+    fn scientific_notation_takes_either_case() {
+        assert_eq!(parse("1.5e3"), parse("1500"));
+        assert_eq!(parse("1.5E3"), parse("1500"));
+        assert_eq!(parse("2.5E-1"), frac!(1, 4));
+        assert_eq!(lex("2E"), vec![Token::Integer(2.into()), Token::Identifier("E".to_string())]);
+    }
+
+    #[test]
+    fn malformed_numbers_are_rejected() {
+        assert!(Lexer::new("1.2.3").tokens().is_err());
+        assert!(Lexer::new(".").tokens().is_err());
+    }
 }
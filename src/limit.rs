@@ -0,0 +1,525 @@
+use std::cmp::Ordering;
+
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::inequality;
+use crate::series::{Series, LOG_VARIABLE};
+use crate::settings;
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power, Function};
+
+// The name of the infinity constant, which is only understood by limits.
+pub const INFINITY: &str = "oo";
+
+// The variable t approaching 0 from above that every limit is rewritten in.
+const LIMIT_VARIABLE: &str = "_t";
+
+// How many times L'Hôpital's rule may be applied in a row.
+const MAX_DEPTH: u32 = 8;
+
+// The orders tried in turn when looking for the leading term of a series.
+const SERIES_ORDERS: [u32; 5] = [2, 4, 8, 16, 32];
+
+// The highest power of ln(t) that may appear in a leading coefficient.
+const MAX_LOG_DEGREE: u32 = 32;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    Both,
+    Left,
+    Right,
+}
+
+// The value of a limit, where infinite values only keep their sign and
+// bounded values that keep oscillating, like sin(x) as x grows, have none.
+#[derive(Debug, PartialEq, Clone)]
+enum Limit {
+    Finite(Expression),
+    Infinite(Ordering),
+    Oscillating,
+}
+
+// Finds the limit of u as x approaches a point, which may be `oo` or `-oo`.
+// Finite points are approached from the given direction, or from both
+// sides when the two one-sided limits have to agree.
+pub fn limit(u: Expression, x: &Expression, point: Expression, direction: Direction) -> Result<Expression, MathError> {
+    let u = u.simplify()?;
+    let point = point.simplify()?;
+    let t = var!(LIMIT_VARIABLE);
+
+    let approach = |v: Expression| -> Result<Limit, MathError> {
+        let w = without_abs(&u.substitute(x, &v).simplify()?, &t, MAX_DEPTH)?;
+        one_sided(&w, &t, MAX_DEPTH)
+            .ok_or_else(|| MathError::NoClosedForm(format!("the limit of `{}` as `{}` approaches `{}`", u, x, point)))
+    };
+
+    let does_not_exist = || MathError::Undefined(format!(
+        "The limit of `{}` as `{}` approaches `{}` does not exist", u, x, point
+    ));
+
+    // Outside of complex mode u cannot be approached from a side on which it
+    // is undefined. When its domain cannot be found, both sides are tried.
+    let defined = |below: bool| {
+        settings::complex_mode() || inequality::defined_beside(&u, x, &point, below).unwrap_or(true)
+    };
+
+    let value = match infinity_sign(&point) {
+        Some(Ordering::Greater) if !defined(true) => return Err(does_not_exist()),
+        Some(Ordering::Greater) => approach(inv!(t.clone()))?,
+        Some(_) if !defined(false) => return Err(does_not_exist()),
+        Some(_) => approach(neg!(inv!(t.clone())))?,
+        None if !point.free_of(&var!(INFINITY)) => {
+            return Err(MathError::Undefined(format!("Cannot approach `{}`", point)))
+        }
+        None => match (direction, defined(true), defined(false)) {
+            (Direction::Right, _, true) | (Direction::Both, false, true) => approach(sum!(point.clone(), t.clone()))?,
+            (Direction::Left, true, _) | (Direction::Both, true, false) => approach(sum!(point.clone(), neg!(t.clone())))?,
+            (Direction::Both, true, true) => {
+                let right = approach(sum!(point.clone(), t.clone()))?;
+                let left = approach(sum!(point.clone(), neg!(t.clone())))?;
+                if left != right {
+                    return Err(does_not_exist())
+                }
+                right
+            }
+            _ => return Err(does_not_exist()),
+        },
+    };
+
+    match value {
+        Limit::Finite(a) => Ok(a),
+        Limit::Infinite(Ordering::Less) => neg!(var!(INFINITY)).simplify(),
+        Limit::Infinite(_) => Ok(var!(INFINITY)),
+        Limit::Oscillating => Err(does_not_exist()),
+    }
+}
+
+// The sign of `oo` or `-oo`, or `None` for any other expression.
+pub fn infinity_sign(u: &Expression) -> Option<Ordering> {
+    match u {
+        Expression::Variable(v) if v.as_str() == INFINITY => Some(Ordering::Greater),
+        Expression::Product(p) => match p.values() {
+            [Expression::Integer(n), Expression::Variable(v)] if v.as_str() == INFINITY && n.num() == &(-1).into()
+                => Some(Ordering::Less),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The limit of u as t approaches 0 from above, read off the leading term of
+// a series when there is one, and otherwise found from the limits of the
+// parts of u.
+fn one_sided(u: &Expression, t: &Expression, depth: u32) -> Option<Limit> {
+    if u.free_of(t) {
+        return Some(Limit::Finite(u.clone()))
+    }
+
+    if let Some((k, c)) = leading(u, t) {
+        return leading_term(&k, &c)
+    }
+
+    match u {
+        Expression::Sum(s) => sum(s.values(), t, depth),
+        Expression::Product(p) => product(p.values(), t, depth),
+        Expression::Power(p) => power(&p.base, &p.exp, t, depth),
+        Expression::Function(f) => function(f, t, depth),
+        _ => None,
+    }
+}
+
+// The first term c*t^k of the series of u in t, if it has one.
+fn leading(u: &Expression, t: &Expression) -> Option<(BigRational, Expression)> {
+    for order in SERIES_ORDERS {
+        let Ok(s) = Series::expand(u, t, &BigRational::from_integer(order.into())) else { break };
+        if let Some(term) = s.terms().first() {
+            return Some(term.clone())
+        }
+    }
+    None
+}
+
+// Replaces |v| by v or -v wherever the sign of v is known as t approaches 0
+// from above, since absolute values have no series at 0.
+fn without_abs(u: &Expression, t: &Expression, depth: u32) -> Result<Expression, MathError> {
+    if u.free_of(t) {
+        return Ok(u.clone())
+    }
+
+    match u {
+        Expression::Sum(s) => Sum::new(s.values().iter().map(|v| without_abs(v, t, depth)).collect::<Result<_, _>>()?).simplify(),
+        Expression::Product(p) => Product::new(p.values().iter().map(|v| without_abs(v, t, depth)).collect::<Result<_, _>>()?).simplify(),
+        Expression::Power(p) => pow!(without_abs(&p.base, t, depth)?, without_abs(&p.exp, t, depth)?).simplify(),
+        Expression::Function(Function::Abs(v)) => {
+            let v = without_abs(v, t, depth)?;
+            match sign_near(&v, t, depth) {
+                Some(Ordering::Greater) => Ok(v),
+                Some(Ordering::Less) => neg!(v).simplify(),
+                _ => func!("abs"; v).simplify(),
+            }
+        }
+        Expression::Function(f) => Expression::Function(f.map(|v| without_abs(v, t, depth))?).simplify(),
+        u => Ok(u.clone()),
+    }
+}
+
+// The sign of u just above t = 0. A u that vanishes there takes the sign of
+// its leading term, or otherwise that of its derivative.
+fn sign_near(u: &Expression, t: &Expression, depth: u32) -> Option<Ordering> {
+    match one_sided(u, t, depth)? {
+        Limit::Finite(a) if a != int!(0) => a.sign(),
+        Limit::Infinite(sign) => Some(sign),
+        Limit::Finite(_) => match leading(u, t) {
+            Some((_, c)) => c.sign(),
+            None => sign_near(&u.derivative(t).ok()?, t, depth.checked_sub(1)?),
+        },
+        Limit::Oscillating => None,
+    }
+}
+
+// The limit of c*t^k, where c may hold powers of ln(t), which tends to -oo.
+fn leading_term(k: &BigRational, c: &Expression) -> Option<Limit> {
+    if k.is_positive() {
+        return Some(Limit::Finite(int!(0)))
+    }
+
+    let l = var!(LOG_VARIABLE);
+    let (mut a, mut degree) = (c.clone().expand().ok()?, 0);
+    while !a.free_of(&l) {
+        if degree == MAX_LOG_DEGREE {
+            return None
+        }
+        a = a.derivative(&l).ok()?;
+        degree += 1;
+    }
+
+    if k.is_zero() && degree == 0 {
+        return Some(Limit::Finite(c.clone()))
+    }
+
+    match (a.sign()?, degree % 2) {
+        (Ordering::Equal, _) => None,
+        (sign, 0) => Some(Limit::Infinite(sign)),
+        (sign, _) => Some(Limit::Infinite(sign.reverse())),
+    }
+}
+
+fn sum(terms: &[Expression], t: &Expression, depth: u32) -> Option<Limit> {
+    let mut finite = Vec::new();
+    let mut infinite = None;
+    let mut oscillating = false;
+
+    for v in terms {
+        match one_sided(v, t, depth)? {
+            Limit::Finite(a) => finite.push(a),
+            Limit::Infinite(sign) => match infinite {
+                Some(other) if other != sign => return None,
+                _ => infinite = Some(sign),
+            },
+            // Two oscillations may cancel, as in sin(x)^2 + cos(x)^2.
+            Limit::Oscillating if oscillating => return None,
+            Limit::Oscillating => oscillating = true,
+        }
+    }
+
+    match infinite {
+        Some(sign) => Some(Limit::Infinite(sign)),
+        None if oscillating => Some(Limit::Oscillating),
+        None => Sum::new(finite).simplify().ok().map(Limit::Finite),
+    }
+}
+
+// A product of vanishing and unbounded factors is rewritten as a quotient
+// for L'Hôpital's rule.
+fn product(factors: &[Expression], t: &Expression, depth: u32) -> Option<Limit> {
+    if let Some(limit) = exponential(factors, t) {
+        return Some(limit)
+    }
+
+    let mut finite = Vec::new();
+    let (mut zero, mut rest) = (Vec::new(), Vec::new());
+    let mut sign = None;
+    let mut oscillating = 0;
+
+    for v in factors {
+        match one_sided(v, t, depth)? {
+            Limit::Finite(a) if a == int!(0) => zero.push(v.clone()),
+            Limit::Finite(a) => {
+                finite.push(a);
+                rest.push(v.clone());
+            }
+            Limit::Infinite(s) => {
+                sign = Some(times(sign.unwrap_or(Ordering::Greater), s));
+                rest.push(v.clone());
+            }
+            Limit::Oscillating => oscillating += 1,
+        }
+    }
+
+    // Oscillations are bounded, so a vanishing factor ends them and any other
+    // finite one keeps them going.
+    if oscillating > 0 {
+        return match (oscillating, sign, zero.is_empty()) {
+            (_, None, false) => Some(Limit::Finite(int!(0))),
+            (1, None, true) => Some(Limit::Oscillating),
+            _ => None,
+        }
+    }
+
+    let Some(mut sign) = sign else {
+        return match zero.is_empty() {
+            true => Product::new(finite).simplify().ok().map(Limit::Finite),
+            false => Some(Limit::Finite(int!(0))),
+        }
+    };
+
+    if !zero.is_empty() {
+        let (f, g): (Expression, Expression) = (Product::new(zero).into(), Product::new(rest).into());
+        return l_hopital(&g, &inv!(f.clone()), t, depth)
+            .or_else(|| l_hopital(&f, &inv!(g), t, depth))
+    }
+
+    for a in finite {
+        sign = times(sign, a.sign()?);
+    }
+    (sign != Ordering::Equal).then_some(Limit::Infinite(sign))
+}
+
+// An exponential whose exponent grows like a power of 1/t outgrows every
+// power of t, so the other factors only decide the sign of the limit.
+fn exponential(factors: &[Expression], t: &Expression) -> Option<Limit> {
+    let (mut exponent, mut rest) = (Vec::new(), Vec::new());
+    for v in factors {
+        match v {
+            Expression::Function(f @ Function::Exp(_)) if !f.arg().free_of(t) => exponent.push(f.arg().clone()),
+            v => rest.push(v.clone()),
+        }
+    }
+
+    if exponent.is_empty() {
+        return None
+    }
+
+    let (k, c) = leading(&Sum::new(exponent).simplify().ok()?, t)?;
+    let (_, a) = leading(&Product::new(rest).simplify().ok()?, t)?;
+    if !k.is_negative() || !c.free_of(&var!(LOG_VARIABLE)) {
+        return None
+    }
+
+    match c.sign()? {
+        Ordering::Less => Some(Limit::Finite(int!(0))),
+        Ordering::Equal => None,
+        Ordering::Greater => match a.sign()? {
+            Ordering::Equal => None,
+            sign => Some(Limit::Infinite(sign)),
+        },
+    }
+}
+
+fn times(a: Ordering, b: Ordering) -> Ordering {
+    match b {
+        Ordering::Less => a.reverse(),
+        Ordering::Equal => Ordering::Equal,
+        Ordering::Greater => a,
+    }
+}
+
+// The limit of n/d through the limit of n'/d', when both n and d vanish or
+// are unbounded.
+fn l_hopital(n: &Expression, d: &Expression, t: &Expression, depth: u32) -> Option<Limit> {
+    if depth == 0 {
+        return None
+    }
+
+    // The rule says nothing when n'/d' oscillates, as for (x + sin(x))/x.
+    let q = prod!(n.derivative(t).ok()?, inv!(d.derivative(t).ok()?)).simplify().ok()?;
+    match one_sided(&q, t, depth - 1)? {
+        Limit::Oscillating => None,
+        limit => Some(limit),
+    }
+}
+
+fn power(base: &Expression, exp: &Expression, t: &Expression, depth: u32) -> Option<Limit> {
+    let limit = match (one_sided(base, t, depth)?, one_sided(exp, t, depth)?) {
+        (Limit::Finite(b), Limit::Finite(e)) if b != int!(0) => {
+            return pow!(b, e).simplify().ok().map(Limit::Finite)
+        }
+        (Limit::Finite(_), Limit::Finite(e)) if e.sign()? == Ordering::Greater => Some(Limit::Finite(int!(0))),
+        (Limit::Finite(b), Limit::Infinite(s)) if b.sign()? != Ordering::Less => {
+            match (sum!(b, int!(-1)).simplify().ok()?.sign()?, s) {
+                (Ordering::Equal, _) => None,
+                (Ordering::Greater, Ordering::Greater) | (Ordering::Less, Ordering::Less) => Some(Limit::Infinite(Ordering::Greater)),
+                _ => Some(Limit::Finite(int!(0))),
+            }
+        }
+        (Limit::Infinite(Ordering::Greater), Limit::Finite(e)) => match e.sign()? {
+            Ordering::Equal => None,
+            Ordering::Greater => Some(Limit::Infinite(Ordering::Greater)),
+            Ordering::Less => Some(Limit::Finite(int!(0))),
+        },
+        (Limit::Infinite(Ordering::Greater), Limit::Infinite(s)) => match s {
+            Ordering::Less => Some(Limit::Finite(int!(0))),
+            _ => Some(Limit::Infinite(Ordering::Greater)),
+        },
+        _ => None,
+    };
+
+    if limit.is_some() {
+        return limit
+    }
+
+    // Otherwise u^v = e^(v*ln(u)).
+    let log = prod!(exp.clone(), ln!(base.clone())).simplify().ok()?;
+    match one_sided(&log, t, depth)? {
+        Limit::Finite(a) => func!("exp"; a).simplify().ok().map(Limit::Finite),
+        Limit::Infinite(Ordering::Less) => Some(Limit::Finite(int!(0))),
+        Limit::Infinite(_) => Some(Limit::Infinite(Ordering::Greater)),
+        Limit::Oscillating => Some(Limit::Oscillating),
+    }
+}
+
+fn function(f: &Function, t: &Expression, depth: u32) -> Option<Limit> {
+    // Nothing is known about the behaviour of functions without rules.
+    if let Function::Other(..) = f {
+        return None
+    }
+
+    // Other bases are changed to e, since a base below 1 reverses the sign.
+    if let Function::Log(b, u) = f {
+        if b.as_ref() != &int!(10) {
+            let quotient = div!(ln!(*u.clone()), ln!(*b.clone())).simplify().ok()?;
+            return one_sided(&quotient, t, depth)
+        }
+    }
+
+    // Functions of several values take the limit of each of them.
+    if f.args().len() > 1 && !matches!(f, Function::Log(..)) {
+        let mut args = Vec::new();
+        for u in f.args() {
+            let Limit::Finite(a) = one_sided(u, t, depth)? else { return None };
+            args.push(a);
+        }
+        return Expression::Function(f.with_args(args)).simplify().ok().map(Limit::Finite)
+    }
+
+    match (f, one_sided(f.arg(), t, depth)?) {
+        (Function::Ln(_) | Function::Log(..), Limit::Finite(a)) if a == int!(0)
+            => Some(Limit::Infinite(Ordering::Less)),
+        (Function::Atanh(_), Limit::Finite(a)) if a == int!(1) || a == int!(-1)
+            => a.sign().map(Limit::Infinite),
+        (_, Limit::Finite(a))
+            => Expression::Function(Function::new(f.name(), a)).simplify().ok().map(Limit::Finite),
+        (Function::Sqrt(_) | Function::Ln(_) | Function::Log(..), Limit::Infinite(Ordering::Greater))
+            => Some(Limit::Infinite(Ordering::Greater)),
+        (Function::Cbrt(_) | Function::Sinh(_) | Function::Asinh(_), Limit::Infinite(sign))
+            => Some(Limit::Infinite(sign)),
+        (Function::Exp(_), Limit::Infinite(Ordering::Less))
+            => Some(Limit::Finite(int!(0))),
+        (Function::Cosh(_) | Function::Abs(_), Limit::Infinite(_)) | (Function::Exp(_) | Function::Acosh(_), Limit::Infinite(Ordering::Greater))
+            => Some(Limit::Infinite(Ordering::Greater)),
+        (Function::Sin(_) | Function::Cos(_), Limit::Infinite(_))
+            => Some(Limit::Oscillating),
+        (Function::Tanh(_), Limit::Infinite(sign))
+            => Some(Limit::Finite(if sign == Ordering::Greater { int!(1) } else { int!(-1) })),
+        (Function::Atan(_), Limit::Infinite(sign)) => {
+            let half = if sign == Ordering::Greater { frac!(1, 2) } else { frac!(-1, 2) };
+            prod!(half, var!("pi")).simplify().ok().map(Limit::Finite)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn infinity_absorbs_finite_values() {
+        assert_eq!(parse("oo + 1"), var!(INFINITY));
+        assert_eq!(parse("x - oo"), parse("-oo"));
+        assert_eq!(parse("-2 oo"), parse("-oo"));
+        assert_eq!(parse("1/oo"), int!(0));
+        assert_eq!(parse("(-oo)^3"), parse("-oo"));
+    }
+
+    #[test]
+    fn indeterminate_forms_have_no_value() {
+        let oo = var!(INFINITY);
+        assert!(sum!(oo.clone(), neg!(oo.clone())).simplify().is_err());
+        assert!(prod!(int!(0), oo.clone()).simplify().is_err());
+        assert!(div!(oo.clone(), oo).simplify().is_err());
+    }
+
+    fn lim(u: &str, point: &str, direction: Direction) -> Result<Expression, MathError> {
+        limit(parse(u), &var!("x"), parse(point), direction)
+    }
+
+    #[test]
+    fn limits_through_series_and_parts() {
+        assert_eq!(lim("sin(x)/x", "0", Direction::Both).unwrap(), int!(1));
+        assert_eq!(lim("x ln(x)", "0", Direction::Right).unwrap(), int!(0));
+        assert_eq!(lim("(x^2 - 1)/(x - 1)", "1", Direction::Both).unwrap(), int!(2));
+        assert_eq!(lim("(2x + 1)/(x - 3)", "oo", Direction::Both).unwrap(), int!(2));
+        assert_eq!(lim("atan(x)", "-oo", Direction::Both).unwrap(), parse("-pi/2"));
+    }
+
+    #[test]
+    fn one_sided_limits_at_poles() {
+        assert_eq!(lim("1/x", "0", Direction::Right).unwrap(), parse("oo"));
+        assert_eq!(lim("1/x", "0", Direction::Left).unwrap(), parse("-oo"));
+        assert!(lim("1/x", "0", Direction::Both).is_err());
+        assert_eq!(lim("1/x^2", "0", Direction::Both).unwrap(), parse("oo"));
+    }
+
+    #[test]
+    fn limits_stay_inside_the_real_domain() {
+        assert!(lim("ln(x)", "0", Direction::Left).is_err());
+        assert!(lim("sqrt(x) + 1", "0", Direction::Left).is_err());
+        assert!(lim("sqrt(x)", "-oo", Direction::Both).is_err());
+        assert_eq!(lim("sqrt(x)", "0", Direction::Both).unwrap(), int!(0));
+        assert_eq!(lim("ln(x)", "0", Direction::Both).unwrap(), parse("-oo"));
+    }
+
+    #[test]
+    fn absolute_values_split_by_sign() {
+        assert_eq!(lim("abs(x)/x", "0", Direction::Right).unwrap(), int!(1));
+        assert_eq!(lim("abs(x)/x", "0", Direction::Left).unwrap(), int!(-1));
+        assert!(matches!(lim("abs(x)/x", "0", Direction::Both), Err(MathError::Undefined(_))));
+        assert_eq!(lim("abs(x^2 - 1)/(x - 1)", "1", Direction::Left).unwrap(), int!(-2));
+        assert_eq!(lim("x/abs(x)", "-oo", Direction::Both).unwrap(), int!(-1));
+    }
+
+    #[test]
+    fn exponentials_outgrow_powers() {
+        assert_eq!(lim("exp(x)/x^10", "oo", Direction::Both).unwrap(), parse("oo"));
+        assert_eq!(lim("x^100 exp(-x)", "oo", Direction::Both).unwrap(), int!(0));
+        assert_eq!(lim("-x^3 exp(x)", "oo", Direction::Both).unwrap(), parse("-oo"));
+        assert_eq!(lim("x^5 exp(1/x)", "0", Direction::Right).unwrap(), parse("oo"));
+    }
+
+    #[test]
+    fn oscillations_have_no_limit() {
+        assert!(matches!(lim("sin(x)", "oo", Direction::Both), Err(MathError::Undefined(_))));
+        assert!(matches!(lim("cos(1/x)", "0", Direction::Right), Err(MathError::Undefined(_))));
+        assert_eq!(lim("sin(x)/x", "oo", Direction::Both).unwrap(), int!(0));
+        assert_eq!(lim("x sin(1/x)", "0", Direction::Both).unwrap(), int!(0));
+        assert_eq!(lim("x + cos(x)", "oo", Direction::Both).unwrap(), parse("oo"));
+    }
+
+    #[test]
+    fn both_sides_are_limited_to_the_domain() {
+        assert_eq!(lim("sqrt(x)", "0", Direction::Both).unwrap(), int!(0));
+        assert_eq!(lim("x^x", "0", Direction::Both).unwrap(), int!(1));
+        assert!(lim("sqrt(x)", "0", Direction::Left).is_err());
+        assert!(lim("x^x", "0", Direction::Left).is_err());
+        assert_eq!(lim("tan(x)", "pi/2", Direction::Left).unwrap(), parse("oo"));
+    }
+
+    #[test]
+    fn functions_without_rules_have_no_limit() {
+        assert!(matches!(lim("floor(x)", "0", Direction::Both), Err(MathError::NoClosedForm(_))));
+    }
+}
@@ -8,6 +8,18 @@ mod traits;
 mod parser;
 mod lexer;
 mod tokens;
+mod float;
+mod command;
+mod factor;
+mod fraction;
+mod inequality;
+mod integrate;
+mod limit;
+mod polynomial;
+mod series;
+mod settings;
+mod solve;
+mod trigsimp;
 
 use lexer::Lexer;
 use parser::Parser;
@@ -19,16 +31,29 @@ fn main() -> Result<(), Box<dyn Error>> {
     loop {
         let mut text = String::new();
         print!("> ");
-        io::stdout().flush().unwrap();
-        io::stdin().read_line(&mut text).unwrap();
+        io::stdout().flush()?;
 
-        let tokens = Lexer::new(text).tokens()?;
-    
-        let expression = Parser::new(tokens).parse()?;
+        if io::stdin().read_line(&mut text)? == 0 {
+            return Ok(())
+        }
+
+        if text.trim().is_empty() {
+            continue;
+        }
 
-        match expression.simplify() {
+        // Errors are reported per expression so that one bad input
+        // does not end the whole session.
+        match evaluate(text) {
             Ok(u) => println!("{}", u),
             Err(e) => println!("{}", e)
         }
     }
 }
+
+fn evaluate(text: String) -> Result<String, Box<dyn Error>> {
+    let tokens = Lexer::new(text).tokens()?;
+
+    let command = Parser::new(tokens).parse_command()?;
+
+    Ok(command.run()?)
+}
@@ -1,7 +1,8 @@
-use crate::types::{self, Integer, Product, Sum, Function, Power, Variable, Rational};
+use crate::types::{self, Integer, Product, Sum, Function, Power, Variable, Rational, Equation, Inequality, Relation, List};
 use crate::tokens::Token;
 use crate::lexer::{Lexer, LexError};
 use crate::expression::Expression;
+use crate::command::Command;
 use core::fmt;
 use std::vec::IntoIter;
 use std::error::Error;
@@ -20,6 +21,8 @@ impl fmt::Display for ParseError {
 pub struct Parser {
     tokens: IntoIter<Token>,
     current_token: Option<Token>,
+    // The number of absolute value bars left open.
+    open_bars: usize,
 }
 
 impl Parser {
@@ -28,6 +31,7 @@ impl Parser {
         Parser {
             current_token: tokens.next(),
             tokens,
+            open_bars: 0,
         }
     }
 
@@ -38,7 +42,7 @@ impl Parser {
     pub fn parse(&mut self) -> Result<Expression, ParseError> {
         match self.current_token {
             Some(_) => {
-                let result = self.addition()?;
+                let result = self.equation()?;
                 
                 match self.current_token {
                     Some(_) => Err(ParseError("Invalid syntax".to_string())),
@@ -49,6 +53,56 @@ impl Parser {
         }
     }
 
+    pub fn parse_command(&mut self) -> Result<Command, ParseError> {
+        let name = match (&self.current_token, self.tokens.as_slice().first()) {
+            (Some(Token::Identifier(name)), Some(Token::LeftParen)) if Command::is_command(name)
+                => name.clone(),
+            _ => return self.parse().map(Command::Simplify),
+        };
+
+        // Skip over the command name and its opening parenthesis.
+        self.advance();
+        self.advance();
+
+        let mut args = vec![self.equation()?];
+        while let Some(Token::Comma) = &self.current_token {
+            self.advance();
+            args.push(self.equation()?);
+        }
+
+        match self.current_token {
+            Some(Token::RightParen) => self.advance(),
+            _ => return Err(ParseError("Expected `)` after arguments".to_string())),
+        }
+
+        match self.current_token {
+            Some(_) => Err(ParseError("Invalid syntax".to_string())),
+            None => Command::new(&name, args).map_err(ParseError),
+        }
+    }
+
+    fn equation(&mut self) -> Result<Expression, ParseError> {
+        let lhs = self.addition()?;
+
+        match self.current_token {
+            Some(Token::Equal) => {
+                self.advance();
+                Ok(Equation::new(lhs, self.addition()?).into())
+            }
+            Some(Token::Less) => self.inequality(lhs, Relation::Less),
+            Some(Token::LessEqual) => self.inequality(lhs, Relation::LessEqual),
+            Some(Token::Greater) => self.inequality(lhs, Relation::Greater),
+            Some(Token::GreaterEqual) => self.inequality(lhs, Relation::GreaterEqual),
+            Some(Token::NotEqual) => self.inequality(lhs, Relation::NotEqual),
+            _ => Ok(lhs),
+        }
+    }
+
+    fn inequality(&mut self, lhs: Expression, relation: Relation) -> Result<Expression, ParseError> {
+        self.advance();
+        Ok(Inequality::new(lhs, relation, self.addition()?).into())
+    }
+
     fn addition(&mut self) -> Result<Expression, ParseError> {
         let mut values = vec![self.multiplication()?];
 
@@ -70,18 +124,25 @@ impl Parser {
     }
 
     fn multiplication(&mut self) -> Result<Expression, ParseError> {
-        let mut values = vec![self.exponentiation()?];
+        let mut values = vec![self.unary()?];
 
         while let Some(token) = &self.current_token {
             match token {
                 Token::Star => {
                     self.advance();
-                    values.push(self.exponentiation()?)
+                    values.push(self.unary()?)
                 } 
                 Token::Slash => {
                     self.advance();
-                    values.push(inv!(self.exponentiation()?));
+                    values.push(inv!(self.unary()?));
+                }
+                // Juxtaposed operands such as `3x` or `(x-1)(x+2)` multiply.
+                Token::Integer(_) | Token::Decimal(_) | Token::Identifier(_) | Token::LeftParen | Token::LeftBrack => {
+                    values.push(self.unary()?)
                 }
+                // A bar after an operand closes an absolute value when one
+                // is open, as in `||x| - 1|`, and otherwise opens one as in `2|x|`.
+                Token::Pipe if self.open_bars == 0 => values.push(self.unary()?),
                 _ => break,
             }
         }
@@ -89,28 +150,28 @@ impl Parser {
         Ok(Product::new(values).into())
     }
 
-    fn exponentiation(&mut self) -> Result<Expression, ParseError> {
-        let mut result = self.unary()?;
-
-        while let Some(Token::Caret) = &self.current_token {
-            self.advance();
-
-            result = match result {
-                Expression::Power(p) => pow!(*p.base, pow!(*p.exp, self.unary()?)),
-                _ => pow!(result, self.unary()?)
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        match self.current_token.clone() {
+            // A leading minus applies to the whole power, so `-x^2` is `-(x^2)`.
+            Some(Token::Minus) => {
+                self.advance();
+                Ok(neg!(self.unary()?))
             }
+            _ => self.exponentiation(),
         }
-
-        Ok(result)
     }
 
-    fn unary(&mut self) -> Result<Expression, ParseError> {
-        match self.current_token.clone() {
-            Some(Token::Minus) => {
+    fn exponentiation(&mut self) -> Result<Expression, ParseError> {
+        let base = self.basic()?;
+
+        // The exponent may itself be signed or a power, as in `2^-1` or the
+        // right-associative `2^3^2`.
+        match self.current_token {
+            Some(Token::Caret) => {
                 self.advance();
-                Ok(neg!(self.basic()?))
+                Ok(pow!(base, self.unary()?))
             }
-            _ => self.basic(),
+            _ => Ok(base),
         }
     }
 
@@ -122,46 +183,172 @@ impl Parser {
             }
             Some(Token::Decimal(x)) => {
                 self.advance();
-                Ok(Rational::from(x).into())
+                x.parse::<Rational>()
+                    .map(Expression::from)
+                    .map_err(|e| ParseError(e.to_string()))
             }
             Some(Token::Identifier(s)) => {
                 self.advance();
-                match self.basic() {
-                    Ok(u) => Ok(func!(s; u)),
-                    Err(..) => Ok(var!(s)),
+                // A name before parentheses is always a call, as in `f(x)`,
+                // so only numbers and closing parentheses multiply what follows.
+                match self.current_token {
+                    Some(Token::LeftParen | Token::LeftBrack) => self.call(&s),
+                    _ => Ok(var!(s)),
                 }
             }
             Some(Token::Pipe) => {
                 self.advance();
+                self.open_bars += 1;
                 let result = self.addition()?;
 
-                if let Some(Token::Pipe) = &self.current_token {
-                    self.advance();
+                match self.current_token {
+                    Some(Token::Pipe) => self.advance(),
+                    _ => return Err(ParseError("Expected `|` after an absolute value".to_string())),
                 }
+                self.open_bars -= 1;
 
                 Ok(func!("abs"; result))
             }
             Some(Token::LeftParen) => {
                 self.advance();
+                let open_bars = std::mem::take(&mut self.open_bars);
                 let result = self.addition()?;
                 
                 if let Some(Token::RightParen) = &self.current_token {
                     self.advance();
                 }
+                self.open_bars = open_bars;
 
                 Ok(result)
             }
             Some(Token::LeftBrack) => {
                 self.advance();
+                let open_bars = std::mem::take(&mut self.open_bars);
                 let result = self.addition()?;
                 
                 if let Some(Token::RightBrack) = &self.current_token {
                     self.advance();
                 }
+                self.open_bars = open_bars;
 
                 Ok(result)
             }
+            Some(Token::LeftBrace) => {
+                self.advance();
+                let mut values = Vec::new();
+
+                while !matches!(self.current_token, Some(Token::RightBrace) | None) {
+                    values.push(self.equation()?);
+                    match self.current_token {
+                        Some(Token::Comma) => self.advance(),
+                        Some(Token::RightBrace) => (),
+                        _ => return Err(ParseError("Expected `,` or `}` in a list".to_string())),
+                    }
+                }
+
+                match self.current_token {
+                    Some(Token::RightBrace) => self.advance(),
+                    _ => return Err(ParseError("Expected `}` after a list".to_string())),
+                }
+
+                Ok(List::new(values).into())
+            }
             _ => Err(ParseError("Invalid syntax".to_string())),
         }
     }
+
+    // A call such as `sin(x)` or `log(2, x)`, whose arguments in parentheses
+    // are separated by commas.
+    fn call(&mut self, name: &str) -> Result<Expression, ParseError> {
+        if !matches!(self.current_token, Some(Token::LeftParen)) {
+            return Ok(func!(name; self.basic()?))
+        }
+
+        self.advance();
+        let open_bars = std::mem::take(&mut self.open_bars);
+        let mut args = vec![self.addition()?];
+        while let Some(Token::Comma) = &self.current_token {
+            self.advance();
+            args.push(self.addition()?);
+        }
+
+        if let Some(Token::RightParen) = &self.current_token {
+            self.advance();
+        }
+        self.open_bars = open_bars;
+
+        Function::from_args(name, args)
+            .map(Expression::Function)
+            .map_err(ParseError)
+    }
 } 
+
+// Lexes, parses and simplifies an expression for the unit tests.
+#[cfg(test)]
+pub fn parse(text: &str) -> Expression {
+    try_parse(text).unwrap()
+}
+
+// Like `parse`, but leaves errors from simplifying to the test.
+#[cfg(test)]
+pub fn try_parse(text: &str) -> Result<Expression, crate::expression::MathError> {
+    use crate::traits::Simplify;
+
+    parse_unsimplified(text).simplify()
+}
+
+// Lexes and parses an expression as typed, for operations such as `domain`
+// that look at it before simplifying.
+#[cfg(test)]
+pub fn parse_unsimplified(text: &str) -> Expression {
+    let tokens = Lexer::new(text.to_string()).tokens().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Simplify;
+
+    #[test]
+    fn unary_minus_binds_looser_than_caret() {
+        assert_eq!(parse("-x^2"), neg!(pow!(var!("x"), int!(2))).simplify().unwrap());
+        assert_eq!(parse("-2^2"), int!(-4));
+        assert_eq!(parse("-x^2-1"), parse("-1-(x^2)"));
+        assert_eq!(parse("(-x)^2"), parse("x^2"));
+    }
+
+    #[test]
+    fn exponents_are_signed_and_right_associative() {
+        assert_eq!(parse("2^-1"), parse("1/2"));
+        assert_eq!(parse("2^3^2"), int!(512));
+        assert_eq!(parse("(2^3)^2"), int!(64));
+    }
+
+    #[test]
+    fn names_before_parentheses_are_calls() {
+        assert!(matches!(parse("f(x)"), Expression::Function(Function::Other(..))));
+        assert!(matches!(parse("x(x + 1)"), Expression::Function(Function::Other(..))));
+        assert_eq!(parse("2(x + 1)"), parse("2*(x + 1)"));
+        assert_eq!(parse("(x - 1)(x + 1)"), parse("(x - 1)*(x + 1)"));
+        assert_eq!(parse("3x^2"), parse("3*x^2"));
+    }
+
+    #[test]
+    fn bars_nest_and_close_in_order() {
+        let abs = |u: Expression| func!("abs"; u).simplify().unwrap();
+        assert_eq!(parse("||x| - 1|"), abs(parse("|x| - 1")));
+        assert_eq!(parse("|x - |y||"), abs(parse("x - |y|")));
+        assert_eq!(parse("2|x|"), parse("2*|x|"));
+        assert_eq!(parse("|x|y"), parse("|x|*y"));
+    }
+
+    #[test]
+    fn calls_take_argument_lists() {
+        let raw = |text: &str| Parser::new(Lexer::new(text.to_string()).tokens().unwrap()).parse();
+        assert!(matches!(parse("log(2, x)"), Expression::Function(Function::Log(..))));
+        assert!(matches!(parse("f(x, y, z)"), Expression::Function(Function::Other(_, ref args)) if args.len() == 3));
+        assert!(raw("sqrt(4, 2)").is_err());
+        assert!(raw("max()").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power};
+
+// A polynomial with rational coefficients in a fixed list of variables,
+// stored as a map from exponent vectors to nonzero coefficients. Exponent
+// vectors compare lexicographically, so the last term is the leading term
+// with the first variable as the main variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    vars: Vec<Expression>,
+    terms: BTreeMap<Vec<u32>, BigRational>,
+}
+
+impl Polynomial {
+    pub fn zero(vars: Vec<Expression>) -> Polynomial {
+        Polynomial { vars, terms: BTreeMap::new() }
+    }
+
+    pub fn constant(vars: Vec<Expression>, c: BigRational) -> Polynomial {
+        let exps = vec![0; vars.len()];
+        Polynomial::zero(vars).with_term(exps, c)
+    }
+
+    pub fn with_term(mut self, exps: Vec<u32>, c: BigRational) -> Polynomial {
+        if !c.is_zero() {
+            self.terms.insert(exps, c);
+        }
+        self
+    }
+
+    // The symbols of an expression in sorted order, leaving out the
+    // constants `e`, `pi`, `i` and `oo`.
+    pub fn variables(u: &Expression) -> Vec<Expression> {
+        let mut vars = Vec::new();
+        Polynomial::collect_variables(u, &mut vars);
+        vars.sort();
+        vars.dedup();
+        vars
+    }
+
+    fn collect_variables(u: &Expression, vars: &mut Vec<Expression>) {
+        match u {
+            Expression::Variable(v) if !matches!(v.as_str(), "e" | "pi" | "i" | "oo") => vars.push(u.clone()),
+            Expression::Sum(s) => s.values().iter().for_each(|v| Polynomial::collect_variables(v, vars)),
+            Expression::Product(p) => p.values().iter().for_each(|v| Polynomial::collect_variables(v, vars)),
+            Expression::Power(p) => {
+                Polynomial::collect_variables(&p.base, vars);
+                Polynomial::collect_variables(&p.exp, vars);
+            }
+            Expression::Function(f) => f.args().into_iter().for_each(|v| Polynomial::collect_variables(v, vars)),
+            Expression::Equation(e) => {
+                Polynomial::collect_variables(&e.lhs, vars);
+                Polynomial::collect_variables(&e.rhs, vars);
+            }
+            Expression::Inequality(e) => {
+                Polynomial::collect_variables(&e.lhs, vars);
+                Polynomial::collect_variables(&e.rhs, vars);
+            }
+            Expression::List(l) => l.values().iter().for_each(|v| Polynomial::collect_variables(v, vars)),
+            _ => (),
+        }
+    }
+
+    // Reads an expression as a polynomial in the given variables, or returns
+    // `None` if any part of it is not a rational number, one of the variables,
+    // or a sum, product or natural power of those.
+    pub fn from_expression(u: &Expression, vars: &[Expression]) -> Option<Polynomial> {
+        let vars = vars.to_vec();
+
+        if let Some(k) = vars.iter().position(|x| x == u) {
+            let mut exps = vec![0; vars.len()];
+            exps[k] = 1;
+            return Some(Polynomial::zero(vars).with_term(exps, BigRational::one()))
+        }
+
+        match u {
+            Expression::Integer(n) => Some(Polynomial::constant(vars, BigRational::from(n.num().clone()))),
+
+            Expression::Rational(q) => Some(Polynomial::constant(vars, BigRational::new(q.num().clone(), q.den().clone()))),
+
+            Expression::Sum(s) => s.values().iter().try_fold(Polynomial::zero(vars.clone()), |acc, v| {
+                Some(acc.add(&Polynomial::from_expression(v, &vars)?))
+            }),
+
+            Expression::Product(p) => p.values().iter().try_fold(Polynomial::constant(vars.clone(), BigRational::one()), |acc, v| {
+                Some(acc.mul(&Polynomial::from_expression(v, &vars)?))
+            }),
+
+            Expression::Power(p) => match p.exp.as_ref() {
+                Expression::Integer(n) if !n.num().is_negative() => {
+                    Some(Polynomial::from_expression(&p.base, &vars)?.pow(n.num().to_u32()?))
+                }
+                _ => None,
+            },
+
+            _ => None,
+        }
+    }
+
+    pub fn to_expression(&self) -> Expression {
+        Sum::new(self.terms.iter().map(|(exps, c)| {
+            let mut factors = vec![Polynomial::number(c)];
+            for (x, &e) in self.vars.iter().zip(exps) {
+                if e > 0 {
+                    factors.push(pow!(x.clone(), int!(e)));
+                }
+            }
+            Expression::Product(Product::new(factors))
+        }).collect()).simplify().expect("polynomials simplify without errors")
+    }
+
+    pub fn number(c: &BigRational) -> Expression {
+        match c.is_integer() {
+            true => int!(c.numer().clone()),
+            false => frac!(c.numer().clone(), c.denom().clone()),
+        }
+    }
+
+    // The value of an integer or rational number, the reverse of `number`.
+    pub fn rational(u: &Expression) -> Option<BigRational> {
+        match u {
+            Expression::Integer(n) => Some(BigRational::from(n.num().clone())),
+            Expression::Rational(q) => Some(BigRational::new(q.num().clone(), q.den().clone())),
+            _ => None,
+        }
+    }
+
+    pub fn vars(&self) -> &[Expression] {
+        &self.vars
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn coefficients(&self) -> impl Iterator<Item = &BigRational> {
+        self.terms.values()
+    }
+
+    // The highest power of the variable at index `var`, with the zero
+    // polynomial having degree zero.
+    pub fn degree(&self, var: usize) -> u32 {
+        self.terms.keys().map(|exps| exps[var]).max().unwrap_or(0)
+    }
+
+    // The coefficient of the leading term in lexicographic order.
+    pub fn leading_coefficient(&self) -> BigRational {
+        self.terms.values().next_back().cloned().unwrap_or_else(BigRational::zero)
+    }
+
+    pub fn coefficient(&self, monomial: &[u32]) -> BigRational {
+        self.terms.get(monomial).cloned().unwrap_or_else(BigRational::zero)
+    }
+
+    // The coefficient of `x^k` as a polynomial in the remaining variables,
+    // where `x` is the variable at index `var`.
+    pub fn coefficient_in(&self, var: usize, k: u32) -> Polynomial {
+        let mut result = Polynomial::zero(self.vars.clone());
+        for (exps, c) in self.terms.iter().filter(|(exps, _)| exps[var] == k) {
+            let mut exps = exps.clone();
+            exps[var] = 0;
+            result.terms.insert(exps, c.clone());
+        }
+        result
+    }
+
+    fn add_term(&mut self, exps: Vec<u32>, c: BigRational) {
+        let sum = self.coefficient(&exps) + c;
+        match sum.is_zero() {
+            true => self.terms.remove(&exps),
+            false => self.terms.insert(exps, sum),
+        };
+    }
+
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let mut result = self.clone();
+        for (exps, c) in &other.terms {
+            result.add_term(exps.clone(), c.clone());
+        }
+        result
+    }
+
+    pub fn sub(&self, other: &Polynomial) -> Polynomial {
+        self.add(&other.scale(&-BigRational::one()))
+    }
+
+    pub fn scale(&self, c: &BigRational) -> Polynomial {
+        let terms = self.terms.iter().map(|(exps, d)| (exps.clone(), d * c));
+        terms.fold(Polynomial::zero(self.vars.clone()), |acc, (exps, d)| acc.with_term(exps, d))
+    }
+
+    pub fn mul(&self, other: &Polynomial) -> Polynomial {
+        let mut result = Polynomial::zero(self.vars.clone());
+        for (a, c) in &self.terms {
+            for (b, d) in &other.terms {
+                let exps = a.iter().zip(b).map(|(i, j)| i + j).collect();
+                result.add_term(exps, c * d);
+            }
+        }
+        result
+    }
+
+    pub fn pow(&self, mut n: u32) -> Polynomial {
+        let mut result = Polynomial::constant(self.vars.clone(), BigRational::one());
+        let mut base = self.clone();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(&base);
+            }
+            n >>= 1;
+            if n > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+
+    // Multivariate division in lexicographic order, returning `(q, r)` with
+    // `self = q * divisor + r` where no term of `r` is divisible by the
+    // leading term of the divisor. Over one variable this is ordinary long
+    // division.
+    pub fn div_rem(&self, divisor: &Polynomial) -> Result<(Polynomial, Polynomial), MathError> {
+        let Some((lead, lc)) = divisor.terms.iter().next_back() else {
+            return Err(MathError::Undefined("Polynomial division by zero".to_string()))
+        };
+
+        let mut p = self.clone();
+        let mut q = Polynomial::zero(self.vars.clone());
+        let mut r = Polynomial::zero(self.vars.clone());
+
+        while let Some((exps, c)) = p.terms.pop_last() {
+            if exps.iter().zip(lead).all(|(i, j)| i >= j) {
+                let shift = exps.iter().zip(lead).map(|(i, j)| i - j).collect();
+                let t = Polynomial::zero(self.vars.clone()).with_term(shift, c / lc);
+                // The leading term cancels exactly, so only the rest of the
+                // divisor needs subtracting.
+                let mut rest = divisor.clone();
+                rest.terms.pop_last();
+                p = p.sub(&t.mul(&rest));
+                q = q.add(&t);
+            }
+            else {
+                r.terms.insert(exps, c);
+            }
+        }
+
+        Ok((q, r))
+    }
+
+    // Divides by a polynomial known to divide this one exactly.
+    pub fn exact_div(&self, divisor: &Polynomial) -> Polynomial {
+        let (q, r) = self.div_rem(divisor).expect("divisor is nonzero");
+        debug_assert!(r.is_zero(), "divisor divides exactly");
+        q
+    }
+
+    // Scales the polynomial so that its leading coefficient is one.
+    pub fn monic(&self) -> Polynomial {
+        match self.is_zero() {
+            true => self.clone(),
+            false => self.scale(&self.leading_coefficient().recip()),
+        }
+    }
+
+    // The monic greatest common divisor of the coefficients of each power of
+    // the variable at index `var`.
+    fn content_in(&self, var: usize) -> Polynomial {
+        (0..=self.degree(var))
+            .map(|k| self.coefficient_in(var, k))
+            .fold(Polynomial::zero(self.vars.clone()), |acc, c| acc.gcd(&c))
+    }
+
+    // The remainder of `lc^m * self` divided by `other` as polynomials in the
+    // variable at index `var`, where `lc` is the leading coefficient of `other`
+    // in that variable. No division of coefficients is needed.
+    fn pseudo_rem(&self, other: &Polynomial, var: usize) -> Polynomial {
+        let n = other.degree(var);
+        let lc = other.coefficient_in(var, n);
+        let mut r = self.clone();
+
+        while !r.is_zero() && r.degree(var) >= n {
+            let m = r.degree(var);
+            let mut shift = vec![0; self.vars.len()];
+            shift[var] = m - n;
+            let t = Polynomial::zero(self.vars.clone())
+                .with_term(shift, BigRational::one())
+                .mul(&r.coefficient_in(var, m));
+            r = r.mul(&lc).sub(&t.mul(other));
+        }
+
+        r
+    }
+
+    // The monic greatest common divisor, found by splitting off the content in
+    // the first variable that appears, recursing on the contents, and running a
+    // primitive remainder sequence on what remains.
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        if self.is_zero() {
+            return other.monic()
+        }
+        if other.is_zero() {
+            return self.monic()
+        }
+
+        let Some(var) = (0..self.vars.len()).find(|&k| self.degree(k) > 0 || other.degree(k) > 0) else {
+            return Polynomial::constant(self.vars.clone(), BigRational::one())
+        };
+
+        let (ca, cb) = (self.content_in(var), other.content_in(var));
+        let mut a = self.exact_div(&ca);
+        let mut b = other.exact_div(&cb);
+        if a.degree(var) < b.degree(var) {
+            mem::swap(&mut a, &mut b);
+        }
+
+        let g = loop {
+            if b.degree(var) == 0 {
+                break Polynomial::constant(self.vars.clone(), BigRational::one())
+            }
+            let r = a.pseudo_rem(&b, var);
+            if r.is_zero() {
+                break b
+            }
+            a = b;
+            b = r.exact_div(&r.content_in(var));
+        };
+
+        ca.gcd(&cb).mul(&g).monic()
+    }
+
+    // The extended Euclidean algorithm for polynomials in one variable,
+    // returning `(s, t, g)` with `s*self + t*other = g` for the monic
+    // greatest common divisor g.
+    pub fn gcdex(&self, other: &Polynomial) -> Result<(Polynomial, Polynomial, Polynomial), MathError> {
+        let zero = Polynomial::zero(self.vars.clone());
+        let one = Polynomial::constant(self.vars.clone(), BigRational::one());
+
+        let (mut r0, mut r1) = (self.clone(), other.clone());
+        let (mut s0, mut s1) = (one.clone(), zero.clone());
+        let (mut t0, mut t1) = (zero, one);
+
+        while !r1.is_zero() {
+            let (q, r) = r0.div_rem(&r1)?;
+            r0 = mem::replace(&mut r1, r);
+            let s = s0.sub(&q.mul(&s1));
+            s0 = mem::replace(&mut s1, s);
+            let t = t0.sub(&q.mul(&t1));
+            t0 = mem::replace(&mut t1, t);
+        }
+
+        let lc = r0.leading_coefficient().recip();
+        Ok((s0.scale(&lc), t0.scale(&lc), r0.scale(&lc)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn poly(text: &str, vars: &[&str]) -> Polynomial {
+        let vars: Vec<Expression> = vars.iter().map(|&x| var!(x)).collect();
+        Polynomial::from_expression(&parse(text).expand().unwrap(), &vars).unwrap()
+    }
+
+    #[test]
+    fn div_rem_is_long_division_in_one_variable() {
+        let (q, r) = poly("x^3 - 2x + 5", &["x"]).div_rem(&poly("x - 1", &["x"])).unwrap();
+        assert_eq!(q, poly("x^2 + x - 1", &["x"]));
+        assert_eq!(r, poly("4", &["x"]));
+
+        let (q, r) = poly("x^2 + 1", &["x"]).div_rem(&poly("2x", &["x"])).unwrap();
+        assert_eq!(q, poly("x/2", &["x"]));
+        assert_eq!(r, poly("1", &["x"]));
+    }
+
+    #[test]
+    fn div_rem_reduces_by_the_leading_term() {
+        let (f, g) = (poly("x^2 y + x y^2 + y^2", &["x", "y"]), poly("x y - 1", &["x", "y"]));
+        let (q, r) = f.div_rem(&g).unwrap();
+        assert_eq!(q.mul(&g).add(&r), f);
+        assert_eq!(q, poly("x + y", &["x", "y"]));
+        assert_eq!(r, poly("x + y^2 + y", &["x", "y"]));
+    }
+
+    #[test]
+    fn div_rem_rejects_a_zero_divisor() {
+        assert!(poly("x", &["x"]).div_rem(&poly("0", &["x"])).is_err());
+    }
+
+    #[test]
+    fn gcd_is_monic() {
+        let g = poly("2x^2 - 2", &["x"]).gcd(&poly("4x^2 + 8x + 4", &["x"]));
+        assert_eq!(g, poly("x + 1", &["x"]));
+        assert_eq!(poly("x^2 + 1", &["x"]).gcd(&poly("x - 1", &["x"])), poly("1", &["x"]));
+        assert_eq!(poly("0", &["x"]).gcd(&poly("3x - 6", &["x"])), poly("x - 2", &["x"]));
+    }
+
+    #[test]
+    fn gcd_of_several_variables() {
+        let vars = ["x", "y"];
+        let g = poly("x^2 - y^2", &vars).gcd(&poly("x^2 + 2x y + y^2", &vars));
+        assert_eq!(g, poly("x + y", &vars));
+        assert_eq!(poly("x y^2 + y^3", &vars).gcd(&poly("x^2 y", &vars)), poly("y", &vars));
+    }
+}
@@ -0,0 +1,467 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_integer::Integer as _;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::limit;
+use crate::polynomial::Polynomial;
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power, Function};
+
+// The symbol standing for ln(t) in the coefficients of a series in t. It
+// appears when taking the logarithm of something that vanishes or blows
+// up at t = 0, and cannot be typed, so it never clashes with user symbols.
+pub const LOG_VARIABLE: &str = "_l";
+
+// The variable that the `series` command expands in, standing for `x - a`.
+const SERIES_VARIABLE: &str = "_s";
+
+// The highest order the `series` command expands to, since the work grows
+// quickly with the number of terms.
+const MAX_SERIES_ORDER: u32 = 50;
+
+// Expands u in powers of `x - a` up to the order term `O((x - a)^n)`, or in
+// powers of `1/x` when a is `oo`.
+pub fn series(u: Expression, x: &Expression, point: Expression, n: u32) -> Result<Expression, MathError> {
+    if n > MAX_SERIES_ORDER {
+        return Err(MathError::Overflow(format!("Cannot expand a series to more than {} terms", MAX_SERIES_ORDER)))
+    }
+
+    let u = u.simplify()?;
+    let point = point.simplify()?;
+    let t = var!(SERIES_VARIABLE);
+
+    if !point.free_of(x) {
+        return Err(MathError::Undefined(format!("Cannot expand around `{}`", point)))
+    }
+
+    let (v, b) = match limit::infinity_sign(&point) {
+        Some(Ordering::Greater) => (inv!(t.clone()), inv!(x.clone())),
+        Some(_) => (neg!(inv!(t.clone())), neg!(inv!(x.clone()))),
+        None if !point.free_of(&var!(limit::INFINITY)) => {
+            return Err(MathError::Undefined(format!("Cannot expand around `{}`", point)))
+        }
+        None => (sum!(point.clone(), t.clone()), sum!(x.clone(), neg!(point))),
+    };
+
+    let order = BigRational::from_integer(n.into());
+    let w = u.substitute(x, &v);
+    let s = w.clone().simplify()
+        .and_then(|w| Series::expand(&w, &t, &order))
+        .map_err(|e| e.map(|s| user_terms(s, &w.clone().simplify().unwrap_or(w), &t, &b)))?;
+    let s = Series::new(s.terms, s.order.min(order))?;
+
+    let log = ln!(b.clone());
+    let mut terms: Vec<_> = s.terms
+        .iter()
+        .map(|(k, c)| prod!(c.substitute(&var!(LOG_VARIABLE), &log), pow!(b.clone(), Polynomial::number(k))))
+        .collect();
+    terms.push(func!("O"; pow!(b, Polynomial::number(&s.order))));
+
+    Sum::new(terms).simplify()
+}
+
+// Errors name parts of w in terms of t, which are written back in terms of
+// x through t = b for the user, falling back to the text of b.
+fn user_terms(mut message: String, w: &Expression, t: &Expression, b: &Expression) -> String {
+    if w.free_of(t) {
+        return message
+    }
+
+    let named = format!("`{}`", w);
+    if message.contains(&named) {
+        let back = w.substitute(t, b).simplify().map_or_else(|_| w.to_string(), |u| u.to_string());
+        return message.replace(&named, &format!("`{}`", back))
+    }
+
+    let parts = match w {
+        Expression::Sum(s) => s.values().iter().collect(),
+        Expression::Product(p) => p.values().iter().collect(),
+        Expression::Power(p) => vec![p.base.as_ref(), p.exp.as_ref()],
+        Expression::Function(f) => f.args(),
+        _ => Vec::new(),
+    };
+    for v in parts {
+        message = user_terms(message, v, t, b);
+    }
+
+    let b = b.clone().simplify().map_or_else(|_| b.to_string(), |b| b.to_string());
+    message.replace(SERIES_VARIABLE, &b)
+}
+
+// A truncated series `c1*t^k1 + c2*t^k2 + ... + O(t^order)` in a variable t
+// near 0 from above, with increasing rational exponents and nonzero
+// coefficients free of t.
+#[derive(Debug, Clone)]
+pub struct Series {
+    terms: Vec<(BigRational, Expression)>,
+    order: BigRational,
+}
+
+impl Series {
+    fn new(mut terms: Vec<(BigRational, Expression)>, order: BigRational) -> Result<Series, MathError> {
+        terms.retain(|(k, _)| k < &order);
+        terms.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut merged: Vec<(BigRational, Expression)> = Vec::new();
+        for (k, c) in terms {
+            match merged.last_mut() {
+                Some((j, d)) if *j == k => *d = sum!(d.clone(), c),
+                _ => merged.push((k, c)),
+            }
+        }
+
+        let mut simplified = Vec::new();
+        for (k, c) in merged {
+            let c = c.simplify()?;
+            if c != int!(0) {
+                simplified.push((k, c));
+            }
+        }
+
+        Ok(Series { terms: simplified, order })
+    }
+
+    fn constant(c: Expression, order: BigRational) -> Result<Series, MathError> {
+        Series::new(vec![(BigRational::zero(), c)], order)
+    }
+
+    pub fn terms(&self) -> &[(BigRational, Expression)] {
+        &self.terms
+    }
+
+    // The exponent of the leading term, or the order if no term is known.
+    fn valuation(&self) -> BigRational {
+        self.terms.first().map_or_else(|| self.order.clone(), |(k, _)| k.clone())
+    }
+
+    fn add(&self, other: &Series) -> Result<Series, MathError> {
+        let terms = self.terms.iter().chain(&other.terms).cloned().collect();
+        Series::new(terms, (&self.order).min(&other.order).clone())
+    }
+
+    fn mul(&self, other: &Series) -> Result<Series, MathError> {
+        let order = (&self.order + other.valuation()).min(&other.order + self.valuation());
+        let mut terms = Vec::new();
+        for (a, c) in &self.terms {
+            for (b, d) in &other.terms {
+                if a + b < order {
+                    terms.push((a + b, prod!(c.clone(), d.clone())));
+                }
+            }
+        }
+        Series::new(terms, order)
+    }
+
+    fn scale(&self, c: &Expression) -> Result<Series, MathError> {
+        let terms = self.terms.iter().map(|(k, d)| (k.clone(), prod!(c.clone(), d.clone()))).collect();
+        Series::new(terms, self.order.clone())
+    }
+
+    fn map(&self, f: impl Fn(Expression) -> Expression) -> Result<Series, MathError> {
+        let terms = self.terms.iter().map(|(k, c)| (k.clone(), f(c.clone()))).collect();
+        Series::new(terms, self.order.clone())
+    }
+
+    // Multiplies by t^k.
+    fn shift(&self, k: &BigRational) -> Series {
+        Series {
+            terms: self.terms.iter().map(|(j, c)| (j + k, c.clone())).collect(),
+            order: &self.order + k,
+        }
+    }
+
+    // Writes the series as `c*t^v*(1 + r)`, where r only has positive exponents.
+    fn split(&self) -> Result<(Expression, BigRational, Series), MathError> {
+        let Some((v, c)) = self.terms.first() else {
+            return Err(MathError::Undefined("Cannot find the leading term of a series".to_string()))
+        };
+
+        let r = self.shift(&-v).scale(&inv!(c.clone()))?;
+        let r = r.add(&Series::constant(int!(-1), r.order.clone())?)?;
+        Ok((c.clone(), v.clone(), r))
+    }
+
+    // The sum of `coeff(k) * r^k` over k, for r with positive exponents,
+    // taking enough powers of r to reach the order of r.
+    fn power_sum(r: &Series, coeff: impl Fn(u32) -> Expression) -> Result<Series, MathError> {
+        let step = r.valuation();
+        let count = (&r.order / &step).ceil().to_u32().unwrap_or(0);
+
+        let mut power = Series::constant(int!(1), r.order.clone())?;
+        let mut result = Series::constant(coeff(0), r.order.clone())?;
+        for k in 1..=count {
+            power = power.mul(r)?;
+            result = result.add(&power.scale(&coeff(k))?)?;
+        }
+        Ok(result)
+    }
+
+    // Raises the series to a power through the binomial series
+    // `(c*t^v*(1 + r))^a = c^a * t^(v*a) * sum (a choose k) r^k`.
+    fn pow(&self, a: &Expression) -> Result<Series, MathError> {
+        let (c, v, r) = self.split()?;
+
+        let shift = match (v.is_zero(), Polynomial::rational(a)) {
+            (true, _) => BigRational::zero(),
+            (false, Some(a)) => v * a,
+            (false, None) => return Err(MathError::Undefined(format!("Cannot expand a power with exponent `{}`", a))),
+        };
+
+        let binomial = |k: u32| {
+            let mut coeff = int!(1);
+            for j in 0..k {
+                coeff = prod!(coeff, sum!(a.clone(), int!(-i64::from(j))), frac!(1, j + 1));
+            }
+            coeff
+        };
+
+        Ok(Series::power_sum(&r, binomial)?.scale(&pow!(c, a.clone()))?.shift(&shift))
+    }
+
+    // `e^(c0 + r) = e^c0 * sum r^k / k!`, defined when no exponent is negative.
+    fn exp(&self) -> Result<Series, MathError> {
+        if self.valuation().is_negative() {
+            return Err(MathError::Undefined("Cannot expand an exponential of a diverging expression".to_string()))
+        }
+
+        let c0 = self.terms.iter().find(|(k, _)| k.is_zero()).map_or(int!(0), |(_, c)| c.clone());
+        let r = self.add(&Series::constant(neg!(c0.clone()), self.order.clone())?)?;
+
+        let factorial = |k: u32| Expression::Rational(Rational::new(1, (1..=k).map(BigInt::from).product::<BigInt>()));
+        Series::power_sum(&r, factorial)?.scale(&func!("exp"; c0))
+    }
+
+    // `sin(c0 + r) = sin(c0)*cos(r) + cos(c0)*sin(r)` and
+    // `cos(c0 + r) = cos(c0)*cos(r) - sin(c0)*sin(r)`, with the odd and even
+    // terms of `sum i^k r^k / k!` giving sin(r) and cos(r).
+    fn sin_cos(&self) -> Result<(Series, Series), MathError> {
+        if self.valuation().is_negative() {
+            return Err(MathError::Undefined("Cannot expand a trigonometric function of a diverging expression".to_string()))
+        }
+
+        let c0 = self.terms.iter().find(|(k, _)| k.is_zero()).map_or(int!(0), |(_, c)| c.clone());
+        let r = self.add(&Series::constant(neg!(c0.clone()), self.order.clone())?)?;
+
+        let factorial = |k: u32| (1..=k).map(BigInt::from).product::<BigInt>();
+        let sin_r = Series::power_sum(&r, |k| match k % 4 {
+            1 => Expression::Rational(Rational::new(1, factorial(k))),
+            3 => Expression::Rational(Rational::new(-1, factorial(k))),
+            _ => int!(0),
+        })?;
+        let cos_r = Series::power_sum(&r, |k| match k % 4 {
+            0 => Expression::Rational(Rational::new(1, factorial(k))),
+            2 => Expression::Rational(Rational::new(-1, factorial(k))),
+            _ => int!(0),
+        })?;
+
+        let (sin_c, cos_c) = (func!("sin"; c0.clone()), func!("cos"; c0));
+        let sin = sin_r.scale(&cos_c)?.add(&cos_r.scale(&sin_c)?)?;
+        let cos = cos_r.scale(&cos_c)?.add(&sin_r.scale(&neg!(sin_c))?)?;
+        Ok((sin, cos))
+    }
+
+    // The odd power series of `atan`, `atanh`, `asin` and `asinh`, which
+    // are only expanded where their argument vanishes.
+    fn inverse(&self, name: &str) -> Result<Series, MathError> {
+        if !self.valuation().is_positive() {
+            return Err(MathError::Undefined(format!("Cannot expand `{}` away from where its argument vanishes", name)))
+        }
+
+        let coeff = |k: u32| {
+            if k.is_even() {
+                return int!(0)
+            }
+            let j = k / 2;
+            let sign = if j.is_odd() && matches!(name, "atan" | "asinh") { -1 } else { 1 };
+            match name {
+                "atan" | "atanh" => frac!(sign, k),
+                // The central binomial coefficient (2j choose j) / 4^j.
+                _ => {
+                    let central = (j + 1..=2 * j).map(BigInt::from).product::<BigInt>()
+                        / (1..=j).map(BigInt::from).product::<BigInt>();
+                    Expression::Rational(Rational::new(sign * central, BigInt::from(4).pow(j) * k))
+                }
+            }
+        };
+        Series::power_sum(self, coeff)
+    }
+
+    // `ln(c*t^v*(1 + r)) = ln(c) + v*ln(t) + sum (-1)^(k+1) r^k / k`.
+    fn ln(&self) -> Result<Series, MathError> {
+        let (c, v, r) = self.split()?;
+        let log = |k: u32| match k {
+            0 => int!(0),
+            k => frac!(if k.is_odd() { 1 } else { -1 }, k),
+        };
+
+        let constant = sum!(ln!(c), prod!(Polynomial::number(&v), var!(LOG_VARIABLE)));
+        Series::power_sum(&r, log)?.add(&Series::constant(constant, r.order.clone())?)
+    }
+
+    // Expands a simplified expression in powers of t near 0 from above, with
+    // every term below t^order.
+    pub fn expand(u: &Expression, t: &Expression, order: &BigRational) -> Result<Series, MathError> {
+        if u.free_of(t) {
+            return Series::constant(u.clone(), order.clone())
+        }
+        // The variable itself is known exactly, so its term is kept even
+        // beyond the order asked for.
+        if u == t {
+            let order = order.max(&BigRational::from_integer(2.into())).clone();
+            return Series::new(vec![(BigRational::one(), int!(1))], order)
+        }
+
+        match u {
+            Expression::Sum(s) => {
+                let mut result = Series::constant(int!(0), order.clone())?;
+                for v in s.values() {
+                    result = result.add(&Series::expand(v, t, order)?)?;
+                }
+                Ok(result)
+            }
+
+            Expression::Product(p) => Series::expand_product(p.values(), t, order),
+
+            Expression::Power(p) => Series::expand_power(&p.base, &p.exp, t, order),
+
+            Expression::Function(f) => match f {
+                Function::Sqrt(v) => Series::expand_power(v, &frac!(1, 2), t, order),
+                Function::Cbrt(v) => Series::expand_power(v, &frac!(1, 3), t, order),
+                Function::Ln(v) => Series::expand_ln(v, t, order),
+                Function::Log(b, v) if b.free_of(t) => Series::expand_ln(v, t, order)?.scale(&inv!(ln!(*b.clone()))),
+                // The variable is real, so these apply to each coefficient.
+                Function::Re(v) | Function::Im(v) | Function::Conj(v)
+                    => Series::expand(v, t, order)?.map(|c| func!(f.name(); c)),
+                Function::Arg(v) => Series::expand_ln(v, t, order)?.map(|c| func!("im"; c)),
+                Function::Exp(v) => Series::expand(v, t, order)?.exp(),
+                Function::Sin(v) => Ok(Series::expand(v, t, order)?.sin_cos()?.0),
+                Function::Cos(v) => Ok(Series::expand(v, t, order)?.sin_cos()?.1),
+                // The others are quotients of these, which may need more terms.
+                Function::Tan(v)
+                    => Series::expand_product(&[func!("sin"; *v.clone()), inv!(func!("cos"; *v.clone()))], t, order),
+                Function::Cot(v)
+                    => Series::expand_product(&[func!("cos"; *v.clone()), inv!(func!("sin"; *v.clone()))], t, order),
+                Function::Sec(v) => Series::expand_power(&func!("cos"; *v.clone()), &int!(-1), t, order),
+                Function::Csc(v) => Series::expand_power(&func!("sin"; *v.clone()), &int!(-1), t, order),
+                Function::Sinh(v) | Function::Cosh(v) => {
+                    let s = Series::expand(v, t, order)?;
+                    let sign = if matches!(f, Function::Sinh(_)) { -1 } else { 1 };
+                    let (up, down) = (s.exp()?, s.scale(&int!(-1))?.exp()?);
+                    up.add(&down.scale(&int!(sign))?)?.scale(&frac!(1, 2))
+                }
+                Function::Tanh(v)
+                    => Series::expand_product(&[func!("sinh"; *v.clone()), inv!(func!("cosh"; *v.clone()))], t, order),
+                Function::Atan(v) | Function::Atanh(v) | Function::Asin(v) | Function::Asinh(v)
+                    => Series::expand(v, t, order)?.inverse(f.name()),
+                Function::Acos(v) => Series::expand(v, t, order)?
+                    .inverse("asin")?
+                    .scale(&int!(-1))?
+                    .add(&Series::constant(prod!(frac!(1, 2), var!("pi")), order.clone())?),
+                _ => Err(MathError::Undefined(format!("Cannot expand `{}` as a series", u))),
+            },
+
+            _ => Err(MathError::Undefined(format!("Cannot expand `{}` as a series", u))),
+        }
+    }
+
+    // Factors with negative exponents cost the others precision, so each
+    // factor is expanded again when the first pass was not deep enough.
+    fn expand_product(factors: &[Expression], t: &Expression, order: &BigRational) -> Result<Series, MathError> {
+        let mut series = factors.iter()
+            .map(|v| Series::expand(v, t, order))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let total: BigRational = series.iter().map(Series::valuation).sum();
+        for (v, s) in factors.iter().zip(series.iter_mut()) {
+            let needed = order - (&total - s.valuation());
+            if needed > s.order {
+                *s = Series::expand(v, t, &needed)?;
+            }
+        }
+
+        let mut result = series.remove(0);
+        for s in &series {
+            result = result.mul(s)?;
+        }
+        Ok(result)
+    }
+
+    fn expand_power(base: &Expression, exp: &Expression, t: &Expression, order: &BigRational) -> Result<Series, MathError> {
+        if base.free_of(t) {
+            return Series::expand(exp, t, order)?.scale(&ln!(base.clone()))?.exp()
+        }
+        if !exp.free_of(t) {
+            let log = prod!(exp.clone(), ln!(base.clone())).simplify()?;
+            return Series::expand(&log, t, order)?.exp()
+        }
+
+        // The result is known to t^(v*a + order of base - v).
+        let mut s = Series::expand(base, t, order)?;
+        if let Some(a) = Polynomial::rational(exp) {
+            let v = s.valuation();
+            let needed = order - &v * a + &v;
+            if needed > s.order {
+                s = Series::expand(base, t, &needed)?;
+            }
+        }
+        s.pow(exp)
+    }
+
+    fn expand_ln(u: &Expression, t: &Expression, order: &BigRational) -> Result<Series, MathError> {
+        // The result is known to t^(order of u - v).
+        let mut s = Series::expand(u, t, order)?;
+        let needed = order + s.valuation();
+        if needed > s.order {
+            s = Series::expand(u, t, &needed)?;
+        }
+        s.ln()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn expand(u: &str, point: &str, n: u32) -> Result<Expression, MathError> {
+        series(parse(u), &var!("x"), parse(point), n)
+    }
+
+    #[test]
+    fn taylor_series_at_zero() {
+        assert_eq!(expand("sin(x)", "0", 6).unwrap(), parse("x - x^3/6 + x^5/120 + O(x^6)"));
+        assert_eq!(expand("1/(1 - x)", "0", 4).unwrap(), parse("1 + x + x^2 + x^3 + O(x^4)"));
+        assert_eq!(expand("sqrt(1 + x)", "0", 3).unwrap(), parse("1 + x/2 - x^2/8 + O(x^3)"));
+    }
+
+    #[test]
+    fn series_around_other_points() {
+        assert_eq!(expand("ln(x)", "1", 3).unwrap(), parse("(x - 1) - (x - 1)^2/2 + O((x - 1)^3)"));
+        assert_eq!(expand("x/(x + 1)", "oo", 3).unwrap(), parse("1 - 1/x + 1/x^2 + O(x^(-3))"));
+    }
+
+    #[test]
+    fn laurent_and_logarithmic_terms() {
+        assert_eq!(expand("exp(x)/x", "0", 2).unwrap(), parse("1/x + 1 + x/2 + O(x^2)"));
+        assert_eq!(expand("x^x", "0", 2).unwrap(), parse("1 + x ln(x) + O(x^2)"));
+    }
+
+    #[test]
+    fn orders_are_capped() {
+        assert!(matches!(expand("exp(x)", "0", 100_000), Err(MathError::Overflow(_))));
+        assert!(expand("exp(x)", "0", MAX_SERIES_ORDER).is_ok());
+    }
+
+    #[test]
+    fn errors_name_the_users_variable() {
+        let message = |u: &str, point: &str| expand(u, point, 6).unwrap_err().to_string();
+        assert_eq!(message("abs(x)", "0"), "Cannot expand `|x|` as a series");
+        assert_eq!(message("abs(x) + 1", "2"), "Cannot expand `|x|` as a series");
+        assert_eq!(message("max(1, x)", "0"), "Cannot expand `max(1, x)` as a series");
+        assert_eq!(message("floor(x)", "oo"), "Cannot expand `floor(x)` as a series");
+    }
+}
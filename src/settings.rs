@@ -0,0 +1,140 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+
+thread_local! {
+    static COMPLEX_MODE: Cell<bool> = const { Cell::new(false) };
+    static ASSUMPTIONS: RefCell<HashMap<String, Assumptions>> = RefCell::new(HashMap::new());
+}
+
+// Whether operations that leave the real numbers, such as even roots and
+// logarithms of negative numbers, give complex results instead of errors.
+pub fn complex_mode() -> bool {
+    COMPLEX_MODE.with(Cell::get)
+}
+
+pub fn set_complex_mode(enabled: bool) {
+    COMPLEX_MODE.with(|mode| mode.set(enabled))
+}
+
+// A property a variable can be declared to have with `assume`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    Real,
+    Integer,
+    Nonzero,
+    Nonnegative,
+    Positive,
+}
+
+impl Property {
+    pub fn from_name(name: &str) -> Option<Property> {
+        match name {
+            "real" => Some(Property::Real),
+            "integer" => Some(Property::Integer),
+            "nonzero" => Some(Property::Nonzero),
+            "nonnegative" => Some(Property::Nonnegative),
+            "positive" => Some(Property::Positive),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Property::Real => write!(f, "real"),
+            Property::Integer => write!(f, "integer"),
+            Property::Nonzero => write!(f, "nonzero"),
+            Property::Nonnegative => write!(f, "nonnegative"),
+            Property::Positive => write!(f, "positive"),
+        }
+    }
+}
+
+// What is known about a variable, where every property also sets the ones
+// it implies, so that a positive variable is nonzero, nonnegative and real.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Assumptions {
+    pub real: bool,
+    pub integer: bool,
+    pub nonzero: bool,
+    pub nonnegative: bool,
+    pub positive: bool,
+}
+
+impl Assumptions {
+    fn with(mut self, property: Property) -> Assumptions {
+        match property {
+            Property::Real => (),
+            Property::Integer => self.integer = true,
+            Property::Nonzero => self.nonzero = true,
+            Property::Nonnegative => self.nonnegative = true,
+            Property::Positive => self.positive = true,
+        }
+        self.real = true;
+        self.positive |= self.nonnegative && self.nonzero;
+        self.nonnegative |= self.positive;
+        self.nonzero |= self.positive;
+        self
+    }
+}
+
+pub fn assumptions(name: &str) -> Assumptions {
+    ASSUMPTIONS.with(|a| a.borrow().get(name).copied().unwrap_or_default())
+}
+
+pub fn assume(name: &str, property: Property) {
+    ASSUMPTIONS.with(|a| {
+        let mut a = a.borrow_mut();
+        let known = a.get(name).copied().unwrap_or_default();
+        a.insert(name.to_string(), known.with(property));
+    })
+}
+
+pub fn forget(name: &str) {
+    ASSUMPTIONS.with(|a| a.borrow_mut().remove(name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Expression;
+    use crate::parser::parse;
+    use crate::types::Integer;
+
+    #[test]
+    fn properties_imply_weaker_ones() {
+        assume("x", Property::Positive);
+        assert_eq!(assumptions("x"), Assumptions { real: true, integer: false, nonzero: true, nonnegative: true, positive: true });
+
+        assume("y", Property::Nonnegative);
+        assume("y", Property::Nonzero);
+        assert!(assumptions("y").positive);
+
+        forget("x");
+        assert_eq!(assumptions("x"), Assumptions::default());
+    }
+
+    #[test]
+    fn rules_wait_for_assumptions() {
+        assert_eq!(parse("ln(x y)"), parse("ln(x*y)"));
+        assert_eq!(parse("sqrt(x^2)"), parse("|x|"));
+        assert_eq!(parse("ln(x^2)"), parse("2 ln(|x|)"));
+
+        assume("x", Property::Positive);
+        assume("y", Property::Positive);
+        assert_eq!(parse("ln(x y)"), parse("ln(x) + ln(y)"));
+        assert_eq!(parse("sqrt(x^2)"), parse("x"));
+        assert_eq!(parse("|x|"), parse("x"));
+    }
+
+    #[test]
+    fn integer_and_nonnegative_symbols() {
+        assume("n", Property::Integer);
+        assert_eq!(parse("(-1)^(2n)"), int!(1));
+
+        assume("w", Property::Nonnegative);
+        assert_eq!(parse("sqrt(w^2)"), parse("w"));
+    }
+}
@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+
+use num_rational::BigRational;
+use num_traits::Zero;
+
+use crate::expression::{Expression, MathError};
+use crate::factor;
+use crate::fraction;
+use crate::polynomial::Polynomial;
+use crate::settings;
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power, Function, Equation};
+
+// The solutions of an equation in one unknown.
+pub enum Solutions {
+    All,
+    Finite(Vec<Expression>),
+}
+
+// Solves a polynomial equation, or an expression taken to equal zero, for
+// x. Polynomials with rational coefficients are factored first, so that
+// rational roots are found at any degree, while the remaining factors up
+// to degree four are solved in radicals. Outside of complex mode only the
+// real solutions are kept.
+pub fn solve(u: Expression, x: &Expression) -> Result<Solutions, MathError> {
+    let u = match u.simplify()? {
+        Expression::Equation(e) => e.zero_form()?,
+        u => u,
+    };
+
+    let (n, _) = fraction::numer_denom(&fraction::cancel(u.clone())?)?;
+    let n = n.expand()?;
+
+    let vars = Polynomial::variables(&n);
+    let Some(p) = Polynomial::from_expression(&n, &vars) else {
+        return Err(MathError::Undefined(format!("Cannot solve `{}` = 0 as a polynomial equation", u)))
+    };
+
+    if p.is_zero() {
+        return Ok(Solutions::All)
+    }
+    let Some(k) = vars.iter().position(|v| v == x) else {
+        return Ok(Solutions::Finite(Vec::new()))
+    };
+
+    let mut solutions = Vec::new();
+    if vars.len() == 1 {
+        for (f, _) in factor::factor_polynomial(&p).1 {
+            solutions.extend(roots(&coefficients(&f, 0))?);
+        }
+    }
+    else {
+        solutions = roots(&coefficients(&p, k))?;
+    }
+
+    solutions.sort();
+    solutions.dedup();
+    Ok(Solutions::Finite(solutions))
+}
+
+// Solves a system of linear equations with rational coefficients for the
+// unknowns xs by Gauss-Jordan elimination. Gives `None` for an inconsistent
+// system, and otherwise binds every unknown that is not free to a value in
+// terms of the free ones, so that a unique solution binds them all.
+pub fn solve_system(equations: &[Expression], xs: &[Expression]) -> Result<Option<Vec<Expression>>, MathError> {
+    let mut rows = Vec::new();
+    for u in equations {
+        let u = match u.clone().simplify()? {
+            Expression::Equation(e) => e.zero_form()?,
+            u => u,
+        }.expand()?;
+
+        let mut coefficients = Vec::new();
+        for x in xs {
+            match Polynomial::rational(&u.derivative(x)?) {
+                Some(c) => coefficients.push(c),
+                None => return Err(MathError::Undefined(format!(
+                    "`{}` = 0 is not linear with rational coefficients", u
+                ))),
+            }
+        }
+
+        let constant = xs.iter().fold(u, |v, x| v.substitute(x, &int!(0)));
+        rows.push((coefficients, neg!(constant).simplify()?));
+    }
+
+    let mut pivots = Vec::new();
+    for col in 0..xs.len() {
+        let r = pivots.len();
+        let Some(p) = (r..rows.len()).find(|&i| !rows[i].0[col].is_zero()) else { continue };
+        rows.swap(r, p);
+
+        let pivot = rows[r].0[col].clone();
+        let (coefficients, b) = &mut rows[r];
+        coefficients.iter_mut().for_each(|c| *c /= &pivot);
+        *b = div!(b.clone(), Polynomial::number(&pivot)).simplify()?;
+
+        let (row, b) = rows[r].clone();
+        for (i, (coefficients, c)) in rows.iter_mut().enumerate() {
+            let f = coefficients[col].clone();
+            if i == r || f.is_zero() {
+                continue;
+            }
+            coefficients.iter_mut().zip(&row).for_each(|(a, p)| *a -= &f * p);
+            *c = sum!(c.clone(), prod!(Polynomial::number(&-f), b.clone())).simplify()?;
+        }
+
+        pivots.push(col);
+    }
+
+    if rows[pivots.len()..].iter().any(|(_, b)| !is_zero(b)) {
+        return Ok(None)
+    }
+
+    let mut bindings = Vec::new();
+    for (i, &col) in pivots.iter().enumerate() {
+        let (coefficients, b) = &rows[i];
+        let mut terms = vec![b.clone()];
+        for j in (0..xs.len()).filter(|j| !pivots.contains(j)) {
+            terms.push(prod!(Polynomial::number(&-&coefficients[j]), xs[j].clone()));
+        }
+        bindings.push(Equation::new(xs[col].clone(), Sum::new(terms).simplify()?.expand()?).into());
+    }
+
+    Ok(Some(bindings))
+}
+
+// The coefficients of a polynomial in the variable at index k, lowest first.
+fn coefficients(p: &Polynomial, k: usize) -> Vec<Expression> {
+    (0..=p.degree(k)).map(|n| p.coefficient_in(k, n).to_expression()).collect()
+}
+
+fn is_zero(u: &Expression) -> bool {
+    u == &int!(0)
+}
+
+// Whether a root involving the square root of u can be real, which is
+// assumed when the sign of u is unknown.
+fn admits_real_root(u: &Expression) -> bool {
+    settings::complex_mode() || u.sign() != Some(Ordering::Less)
+}
+
+// The roots of `c[0] + c[1]*x + ... + c[n]*x^n`.
+fn roots(c: &[Expression]) -> Result<Vec<Expression>, MathError> {
+    match c {
+        [] | [_] => Ok(Vec::new()),
+        [b, a] => Ok(vec![neg!(div!(b.clone(), a.clone())).simplify()?]),
+        [c, b, a] => quadratic(a, b, c),
+        [d, c, b, a] => cubic(a, b, c, d),
+        [e, d, c, b, a] => quartic(a, b, c, d, e),
+        _ => Err(MathError::NoClosedForm(format!(
+            "the roots of a polynomial of degree {} in radicals", c.len() - 1
+        ))),
+    }
+}
+
+fn quadratic(a: &Expression, b: &Expression, c: &Expression) -> Result<Vec<Expression>, MathError> {
+    let d = sum!(pow!(b.clone(), int!(2)), prod!(int!(-4), a.clone(), c.clone())).simplify()?;
+    if !admits_real_root(&d) {
+        return Ok(Vec::new())
+    }
+
+    let root = |s: i64| div!(sum!(neg!(b.clone()), prod!(int!(s), sqrt!(d.clone()))), prod!(int!(2), a.clone())).simplify();
+    match is_zero(&d) {
+        true => Ok(vec![root(1)?]),
+        false => Ok(vec![root(-1)?, root(1)?]),
+    }
+}
+
+// Cardano's formula for the depressed cubic `t^3 + p*t + q` with
+// `x = t - b/(3a)`. Three distinct real roots cannot be written with real
+// radicals, so outside of complex mode they are given in the trigonometric
+// form `t = 2 sqrt(-p/3) cos(acos(3q/(2p) sqrt(-3/p))/3 - 2πk/3)`.
+fn cubic(a: &Expression, b: &Expression, c: &Expression, d: &Expression) -> Result<Vec<Expression>, MathError> {
+    let shift = div!(b.clone(), prod!(int!(3), a.clone())).simplify()?;
+    let p = div!(
+        sum!(prod!(int!(3), a.clone(), c.clone()), neg!(pow!(b.clone(), int!(2)))),
+        prod!(int!(3), pow!(a.clone(), int!(2)))
+    ).simplify()?;
+    let q = div!(
+        sum!(
+            prod!(int!(2), pow!(b.clone(), int!(3))),
+            prod!(int!(-9), a.clone(), b.clone(), c.clone()),
+            prod!(int!(27), pow!(a.clone(), int!(2)), d.clone()),
+        ),
+        prod!(int!(27), pow!(a.clone(), int!(3)))
+    ).simplify()?;
+
+    let unshift = |ts: Vec<Expression>| -> Result<Vec<Expression>, MathError> {
+        ts.into_iter().map(|t| sum!(t, neg!(shift.clone())).simplify()).collect()
+    };
+
+    // A primitive cube root of unity, and its conjugate.
+    let omega = |s: i64| sum!(frac!(-1, 2), prod!(frac!(s, 2), sqrt!(int!(3)), imag!())).simplify();
+
+    if is_zero(&p) {
+        let w = cbrt!(neg!(q.clone())).simplify()?;
+        return match (is_zero(&q), settings::complex_mode()) {
+            (true, _) => unshift(vec![int!(0)]),
+            (false, false) => unshift(vec![w]),
+            (false, true) => unshift(vec![w.clone(), prod!(omega(1)?, w.clone()), prod!(omega(-1)?, w)]),
+        }
+    }
+
+    let delta = sum!(
+        prod!(frac!(1, 4), pow!(q.clone(), int!(2))),
+        prod!(frac!(1, 27), pow!(p.clone(), int!(3))),
+    ).simplify()?;
+
+    if is_zero(&delta) {
+        return unshift(vec![
+            div!(prod!(int!(3), q.clone()), p.clone()),
+            div!(prod!(int!(-3), q.clone()), prod!(int!(2), p.clone())),
+        ])
+    }
+
+    if delta.sign() == Some(Ordering::Less) && !settings::complex_mode() {
+        let scale = prod!(int!(2), sqrt!(div!(p.clone(), int!(-3))));
+        let angle = prod!(
+            frac!(1, 3),
+            func!("acos"; prod!(div!(prod!(int!(3), q.clone()), prod!(int!(2), p.clone())), sqrt!(div!(int!(-3), p.clone())))),
+        );
+        let root = |k: i64| prod!(scale.clone(), func!("cos"; sum!(angle.clone(), prod!(frac!(-2 * k, 3), var!("pi")))));
+        return unshift(vec![root(0), root(1), root(2)])
+    }
+
+    let half = neg!(prod!(frac!(1, 2), q.clone()));
+    let u = match delta.sign() {
+        Some(Ordering::Less) => pow!(sum!(half, sqrt!(delta)), frac!(1, 3)).simplify()?,
+        _ => cbrt!(sum!(half, sqrt!(delta))).simplify()?,
+    };
+    let v = neg!(div!(p, prod!(int!(3), u.clone())));
+
+    match settings::complex_mode() {
+        false => unshift(vec![sum!(u, v)]),
+        true => unshift(vec![
+            sum!(u.clone(), v.clone()),
+            sum!(prod!(omega(1)?, u.clone()), prod!(omega(-1)?, v.clone())),
+            sum!(prod!(omega(-1)?, u), prod!(omega(1)?, v)),
+        ]),
+    }
+}
+
+// Ferrari's method for the depressed quartic `y^4 + p*y^2 + q*y + r` with
+// `x = y - b/(4a)`, which splits into two quadratics through a root m of
+// the resolvent cubic `8m^3 + 8p*m^2 + (2p^2 - 8r)*m - q^2`.
+fn quartic(a: &Expression, b: &Expression, c: &Expression, d: &Expression, e: &Expression) -> Result<Vec<Expression>, MathError> {
+    let shift = div!(b.clone(), prod!(int!(4), a.clone())).simplify()?;
+    let p = div!(
+        sum!(prod!(int!(8), a.clone(), c.clone()), prod!(int!(-3), pow!(b.clone(), int!(2)))),
+        prod!(int!(8), pow!(a.clone(), int!(2)))
+    ).simplify()?;
+    let q = div!(
+        sum!(
+            pow!(b.clone(), int!(3)),
+            prod!(int!(-4), a.clone(), b.clone(), c.clone()),
+            prod!(int!(8), pow!(a.clone(), int!(2)), d.clone()),
+        ),
+        prod!(int!(8), pow!(a.clone(), int!(3)))
+    ).simplify()?;
+    let r = div!(
+        sum!(
+            prod!(int!(-3), pow!(b.clone(), int!(4))),
+            prod!(int!(256), pow!(a.clone(), int!(3)), e.clone()),
+            prod!(int!(-64), pow!(a.clone(), int!(2)), b.clone(), d.clone()),
+            prod!(int!(16), a.clone(), pow!(b.clone(), int!(2)), c.clone()),
+        ),
+        prod!(int!(256), pow!(a.clone(), int!(4)))
+    ).simplify()?;
+
+    let mut ys = Vec::new();
+    if is_zero(&q) {
+        // A quadratic in y^2.
+        for z in quadratic(&int!(1), &p, &r)? {
+            if is_zero(&z) {
+                ys.push(int!(0));
+            }
+            else if admits_real_root(&z) {
+                ys.push(neg!(sqrt!(z.clone())));
+                ys.push(sqrt!(z));
+            }
+        }
+    }
+    else {
+        let resolvent = cubic(
+            &int!(8),
+            &prod!(int!(8), p.clone()),
+            &sum!(prod!(int!(2), pow!(p.clone(), int!(2))), prod!(int!(-8), r.clone())),
+            &neg!(pow!(q.clone(), int!(2))),
+        )?;
+        let Some(m) = resolvent.into_iter().find(|m| !is_zero(m) && admits_real_root(m)) else {
+            return Err(MathError::NoClosedForm("the roots of a quartic in radicals".to_string()))
+        };
+
+        let s = sqrt!(prod!(int!(2), m.clone())).simplify()?;
+        let base = sum!(prod!(frac!(1, 2), p), m);
+        let offset = div!(q, prod!(int!(2), s.clone()));
+        ys.extend(quadratic(&int!(1), &s, &sum!(base.clone(), neg!(offset.clone())).simplify()?)?);
+        ys.extend(quadratic(&int!(1), &neg!(s), &sum!(base, offset).simplify()?)?);
+    }
+
+    ys.into_iter().map(|y| sum!(y, neg!(shift.clone())).simplify()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn roots_of(u: &str) -> Result<Vec<Expression>, MathError> {
+        match solve(parse(u), &var!("x"))? {
+            Solutions::Finite(roots) => Ok(roots),
+            Solutions::All => panic!("`{}` vanishes everywhere", u),
+        }
+    }
+
+    // Whether every root makes u vanish to 64 bits.
+    fn all_vanish(u: &str, roots: &[Expression]) -> bool {
+        roots.iter().all(|r| {
+            let value = parse(u).substitute(&var!("x"), r).evaluate(128).unwrap();
+            value.near_integer(64) == Some(0.into())
+        })
+    }
+
+    fn system(equations: &[&str], unknowns: &[&str]) -> Option<Vec<Expression>> {
+        let equations: Vec<_> = equations.iter().map(|u| parse(u)).collect();
+        let unknowns: Vec<_> = unknowns.iter().map(|&x| var!(x)).collect();
+        solve_system(&equations, &unknowns).unwrap()
+    }
+
+    #[test]
+    fn systems_with_a_unique_solution() {
+        assert_eq!(system(&["2x + y = 3", "x - y = 0"], &["x", "y"]), Some(vec![parse("x = 1"), parse("y = 1")]));
+        assert_eq!(
+            system(&["x + y + z = 6", "x - y = 0", "2z = x"], &["x", "y", "z"]),
+            Some(vec![parse("x = 12/5"), parse("y = 12/5"), parse("z = 6/5")]),
+        );
+        assert_eq!(system(&["x/2 + a = 0"], &["x"]), Some(vec![parse("x = -2a")]));
+    }
+
+    #[test]
+    fn systems_with_many_or_no_solutions() {
+        assert_eq!(system(&["x + y = 1", "2x + 2y = 2"], &["x", "y"]), Some(vec![parse("x = 1 - y")]));
+        assert_eq!(system(&["x + y = 1", "x + y = 2"], &["x", "y"]), None);
+    }
+
+    #[test]
+    fn systems_must_be_linear() {
+        assert!(solve_system(&[parse("x*y = 1")], &[var!("x"), var!("y")]).is_err());
+        assert!(solve_system(&[parse("x^2 = 1")], &[var!("x")]).is_err());
+    }
+
+    #[test]
+    fn cubics_with_three_real_roots_use_cosines() {
+        assert_eq!(roots_of("x^3 - 3x + 1").unwrap(), vec![
+            parse("2 cos(2pi/9)"), parse("2 cos(4pi/9)"), parse("2 cos(8pi/9)"),
+        ]);
+
+        let roots = roots_of("x^3 - 2x - 1/2").unwrap();
+        assert_eq!(roots.len(), 3);
+        assert!(all_vanish("x^3 - 2x - 1/2", &roots));
+    }
+
+    #[test]
+    fn quartics_with_rational_roots() {
+        assert_eq!(roots_of("x^4 - 5x^2 + 4").unwrap(), vec![int!(-2), int!(-1), int!(1), int!(2)]);
+    }
+
+    #[test]
+    fn quartics_without_real_roots() {
+        assert_eq!(roots_of("x^4 + x + 1").unwrap(), Vec::new());
+        assert_eq!(roots_of("x^4 + x^2 + x + 5").unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn quartics_with_four_irrational_roots() {
+        let roots = roots_of("x^4 - 10x^2 + x + 1").unwrap();
+        assert_eq!(roots.len(), 4);
+        assert!(all_vanish("x^4 - 10x^2 + x + 1", &roots));
+    }
+}
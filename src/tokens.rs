@@ -1,7 +1,9 @@
+use num_bigint::BigInt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
-    Integer(i32),
-    Decimal(f32),
+    Integer(BigInt),
+    Decimal(String),
     Identifier(String),
     Plus,
     Minus,
@@ -13,4 +15,13 @@ pub enum Token {
     RightParen,
     LeftBrack,
     RightBrack,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
 }
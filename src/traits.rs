@@ -1,5 +1,18 @@
-use crate::expression::{Expression, UndefinedError};
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
 
 pub trait Simplify {
-    fn simplify(self) -> Result<Expression, UndefinedError>;
+    fn simplify(self) -> Result<Expression, MathError>;
+}
+
+pub trait Evaluate {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError>;
+}
+
+pub trait Expand {
+    fn expand(self) -> Result<Expression, MathError>;
+}
+
+pub trait Differentiate {
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError>;
 }
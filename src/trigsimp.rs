@@ -0,0 +1,525 @@
+use std::cmp::Ordering;
+
+use num_traits::{Signed, ToPrimitive};
+
+use crate::expression::{Expression, MathError};
+use crate::traits::Simplify;
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power, Function, Complex, Equation, List};
+
+// Largest number of rounds of rewriting `trigsimp` applies to one subexpression.
+const MAX_ROUNDS: u32 = 16;
+
+type Rule = fn(&Expression) -> Result<Expression, MathError>;
+
+// The rewrites `trigsimp` tries, each of which is kept only when the result
+// is smaller than what it started from.
+const RULES: [Rule; 14] = [
+    |u| squares(u, "cos", |a| sum!(int!(1), neg!(pow!(func!("sin"; a), int!(2))))),
+    |u| squares(u, "sin", |a| sum!(int!(1), neg!(pow!(func!("cos"; a), int!(2))))),
+    |u| squares(u, "tan", |a| sum!(pow!(func!("sec"; a), int!(2)), int!(-1))),
+    |u| squares(u, "sec", |a| sum!(int!(1), pow!(func!("tan"; a), int!(2)))),
+    |u| squares(u, "cot", |a| sum!(pow!(func!("csc"; a), int!(2)), int!(-1))),
+    |u| squares(u, "csc", |a| sum!(int!(1), pow!(func!("cot"; a), int!(2)))),
+    |u| squares(u, "cosh", |a| sum!(int!(1), pow!(func!("sinh"; a), int!(2)))),
+    |u| squares(u, "sinh", |a| sum!(pow!(func!("cosh"; a), int!(2)), int!(-1))),
+    reduce_powers,
+    double_angles,
+    expand_angles,
+    product_to_sum,
+    sum_to_product,
+    |u| to_sin_cos(u).and_then(from_sin_cos),
+];
+
+// The forms `rewrite` converts between.
+pub enum Form {
+    Exp,
+    Trig,
+    Ln,
+}
+
+// Simplifies trigonometric expressions with the Pythagorean identities,
+// double angle and power reduction formulas, and the product-to-sum and
+// sum-to-product formulas. Every subexpression is rewritten greedily,
+// keeping whichever form is the smallest.
+pub fn trigsimp(u: Expression) -> Result<Expression, MathError> {
+    simplify_node(u.simplify()?)
+}
+
+// Writes trigonometric and hyperbolic functions as exponentials,
+// exponentials with imaginary exponents as cosines and sines, or logarithms
+// to any base and the inverse functions as natural logarithms.
+pub fn rewrite(u: Expression, form: Form) -> Result<Expression, MathError> {
+    let u = u.simplify()?;
+    match form {
+        Form::Exp => map(&u, &to_exp)?.simplify(),
+        Form::Trig => map(&u, &from_exp)?.simplify()?.expand()?.simplify(),
+        Form::Ln => map(&u, &to_ln)?.simplify(),
+    }
+}
+
+fn simplify_node(u: Expression) -> Result<Expression, MathError> {
+    let u = match u {
+        Expression::Sum(s) => Sum::new(s.values().iter().cloned().map(simplify_node).collect::<Result<_, _>>()?).simplify()?,
+        Expression::Product(p) => Product::new(p.values().iter().cloned().map(simplify_node).collect::<Result<_, _>>()?).simplify()?,
+        Expression::Power(p) => pow!(simplify_node(*p.base)?, simplify_node(*p.exp)?).simplify()?,
+        Expression::Function(f) => f.map(|v| simplify_node(v.clone()))?.simplify()?,
+        Expression::Equation(e) => Equation::new(simplify_node(*e.lhs)?, simplify_node(*e.rhs)?).into(),
+        Expression::List(l) => List::new(l.values().iter().cloned().map(simplify_node).collect::<Result<_, _>>()?).into(),
+        u => return Ok(u),
+    };
+
+    let mut best = u;
+    for _ in 0..MAX_ROUNDS {
+        let mut improved = false;
+        for rule in RULES {
+            // A rewrite may run into a value where a function is undefined,
+            // which only rules it out.
+            let Ok(v) = rule(&best)
+                .and_then(Expression::simplify)
+                .and_then(|v| canonical_angles(&v))
+                .and_then(Expression::simplify) else { continue };
+            let expanded = v.clone().expand().unwrap_or_else(|_| v.clone());
+            let v = if size(&expanded) < size(&v) { expanded } else { v };
+
+            if size(&v) < size(&best) {
+                best = v;
+                improved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    Ok(best)
+}
+
+// Writes odd and even functions of a sum that leads with a minus sign
+// through the negated sum, as in `sin(y - x) = -sin(x - y)`, so that both
+// forms of an angle meet as like terms.
+fn canonical_angles(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| {
+        let Expression::Function(f) = v else { return Ok(None) };
+        let Expression::Sum(s) = f.arg() else { return Ok(None) };
+        let negative = |c: &Expression| matches!(c, Expression::Integer(_) | Expression::Rational(_)) && c.sign() == Some(Ordering::Less);
+        let leads_with_minus = match s.values().first() {
+            Some(Expression::Product(p)) => p.values().first().is_some_and(negative),
+            Some(c) => negative(c),
+            None => false,
+        };
+        if !leads_with_minus {
+            return Ok(None)
+        }
+
+        let w = func!(f.name(); neg!(f.arg().clone()).expand()?);
+        match f {
+            Function::Sin(_) | Function::Tan(_) | Function::Cot(_) | Function::Csc(_) | Function::Sinh(_) | Function::Tanh(_)
+                => Ok(Some(neg!(w))),
+            Function::Cos(_) | Function::Sec(_) | Function::Cosh(_) => Ok(Some(w)),
+            _ => Ok(None),
+        }
+    })
+}
+
+// The number of nodes in an expression, which measures how simple it is.
+fn size(u: &Expression) -> usize {
+    match u {
+        Expression::Sum(s) => 1 + s.values().iter().map(size).sum::<usize>(),
+        Expression::Product(p) => 1 + p.values().iter().map(size).sum::<usize>(),
+        Expression::Power(p) => 1 + size(&p.base) + size(&p.exp),
+        Expression::Function(f) => 1 + f.args().into_iter().map(size).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+// Rebuilds u from the bottom up, replacing every subexpression for which
+// f gives a new value. The result is left unsimplified.
+fn map(u: &Expression, f: &dyn Fn(&Expression) -> Result<Option<Expression>, MathError>) -> Result<Expression, MathError> {
+    let u = match u {
+        Expression::Sum(s) => Sum::new(s.values().iter().map(|v| map(v, f)).collect::<Result<_, _>>()?).into(),
+        Expression::Product(p) => Product::new(p.values().iter().map(|v| map(v, f)).collect::<Result<_, _>>()?).into(),
+        Expression::Power(p) => pow!(map(&p.base, f)?, map(&p.exp, f)?),
+        Expression::Function(g) => Expression::Function(g.map(|v| map(v, f))?),
+        u => u.clone(),
+    };
+    Ok(f(&u)?.unwrap_or(u))
+}
+
+// Recognises `f(a)^n` with a positive integer n, taking a bare `f(a)` as n = 1.
+fn trig_power(u: &Expression) -> Option<(&str, &Expression, u32)> {
+    match u {
+        Expression::Function(f) => Some((f.name(), f.arg(), 1)),
+        Expression::Power(p) => match (p.base.as_ref(), p.exp.as_ref()) {
+            (Expression::Function(f), Expression::Integer(n)) if n.num().is_positive()
+                => Some((f.name(), f.arg(), n.num().to_u32()?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Replaces `f(a)^n` for n of at least two by `r(a)^(n/2) * f(a)^(n mod 2)`,
+// where r(a) is an identity for the square of f(a).
+fn squares(u: &Expression, name: &str, r: fn(Expression) -> Expression) -> Result<Expression, MathError> {
+    map(u, &|v| match trig_power(v) {
+        Some((f, a, n)) if f == name && n >= 2 => Ok(Some(prod!(
+            pow!(r(a.clone()), int!(n / 2)),
+            pow!(func!(name; a.clone()), int!(n % 2)),
+        ))),
+        _ => Ok(None),
+    })
+}
+
+// `sin(a)^2 = (1 - cos(2a))/2` and `cos(a)^2 = (1 + cos(2a))/2`.
+fn reduce_powers(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| {
+        let Some((f, a, n)) = trig_power(v) else { return Ok(None) };
+        let sign = match f {
+            "sin" => -1,
+            "cos" => 1,
+            _ => return Ok(None),
+        };
+        if n < 2 {
+            return Ok(None)
+        }
+
+        let cos_2a = func!("cos"; prod!(int!(2), a.clone()));
+        let half = prod!(frac!(1, 2), sum!(int!(1), prod!(int!(sign), cos_2a)));
+        Ok(Some(prod!(pow!(half, int!(n / 2)), pow!(func!(f; a.clone()), int!(n % 2)))))
+    })
+}
+
+// `sin(a) * cos(a) = sin(2a)/2` within a product.
+fn double_angles(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| {
+        let Expression::Product(p) = v else { return Ok(None) };
+        let factors = p.values();
+
+        for (i, s) in factors.iter().enumerate() {
+            let Some(("sin", a, m)) = trig_power(s) else { continue };
+            let Some(j) = factors.iter().position(|c| matches!(trig_power(c), Some(("cos", b, _)) if b == a)) else { continue };
+            let Some((_, _, n)) = trig_power(&factors[j]) else { continue };
+
+            let k = m.min(n);
+            let mut rest: Vec<_> = factors.iter()
+                .enumerate()
+                .filter(|(l, _)| *l != i && *l != j)
+                .map(|(_, c)| c.clone())
+                .collect();
+            rest.push(pow!(prod!(frac!(1, 2), func!("sin"; prod!(int!(2), a.clone()))), int!(k)));
+            rest.push(pow!(func!("sin"; a.clone()), int!(m - k)));
+            rest.push(pow!(func!("cos"; a.clone()), int!(n - k)));
+            return Ok(Some(Product::new(rest).into()))
+        }
+        Ok(None)
+    })
+}
+
+// Expands sines and cosines of sums and of integer multiples with the
+// angle addition formulas.
+fn expand_angles(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| match v {
+        Expression::Function(f @ (Function::Sin(a) | Function::Cos(a))) => match expand_angle(f.name(), a)? {
+            w if &w == v => Ok(None),
+            w => Ok(Some(w)),
+        },
+        _ => Ok(None),
+    })
+}
+
+fn expand_angle(name: &str, a: &Expression) -> Result<Expression, MathError> {
+    let (x, y) = match a {
+        Expression::Sum(s) => (s.values()[0].clone(), Sum::new(s.values()[1..].to_vec()).simplify()?),
+        Expression::Product(p) => match p.values() {
+            [Expression::Integer(n), rest @ ..] if n.num() > &1.into() => {
+                let x = Product::new(rest.to_vec()).simplify()?;
+                let y = prod!(Expression::Integer(Integer::new(n.num() - 1)), x.clone()).simplify()?;
+                (x, y)
+            }
+            _ => return Ok(func!(name; a.clone())),
+        },
+        _ => return Ok(func!(name; a.clone())),
+    };
+
+    let (sin_x, cos_x) = (expand_angle("sin", &x)?, expand_angle("cos", &x)?);
+    let (sin_y, cos_y) = (expand_angle("sin", &y)?, expand_angle("cos", &y)?);
+    match name {
+        "sin" => Ok(sum!(prod!(sin_x, cos_y), prod!(cos_x, sin_y))),
+        _ => Ok(sum!(prod!(cos_x, cos_y), neg!(prod!(sin_x, sin_y)))),
+    }
+}
+
+// Turns the first product of a sine or cosine with another into a sum.
+fn product_to_sum(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| {
+        let Expression::Product(p) = v else { return Ok(None) };
+        let factors = p.values();
+        let is_trig = |c: &Expression| matches!(c, Expression::Function(Function::Sin(_) | Function::Cos(_)));
+
+        let Some(i) = factors.iter().position(is_trig) else { return Ok(None) };
+        let Some(j) = factors.iter().skip(i + 1).position(is_trig).map(|j| i + 1 + j) else { return Ok(None) };
+        let (Expression::Function(f), Expression::Function(g)) = (&factors[i], &factors[j]) else { return Ok(None) };
+
+        let (a, b) = (f.arg().clone(), g.arg().clone());
+        let plus = sum!(a.clone(), b.clone());
+        let minus = sum!(a.clone(), neg!(b.clone()));
+        let value = match (f.name(), g.name()) {
+            ("sin", "sin") => sum!(func!("cos"; minus), neg!(func!("cos"; plus))),
+            ("cos", "cos") => sum!(func!("cos"; minus), func!("cos"; plus)),
+            ("sin", _) => sum!(func!("sin"; plus), func!("sin"; minus)),
+            _ => sum!(func!("sin"; plus), neg!(func!("sin"; minus))),
+        };
+
+        let mut rest: Vec<_> = factors.iter()
+            .enumerate()
+            .filter(|(l, _)| *l != i && *l != j)
+            .map(|(_, c)| c.clone())
+            .collect();
+        rest.push(prod!(frac!(1, 2), value));
+        Ok(Some(Product::new(rest).into()))
+    })
+}
+
+// Turns a sum or difference of two sines or two cosines into a product.
+fn sum_to_product(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| {
+        let Expression::Sum(s) = v else { return Ok(None) };
+        let [t1, t2] = s.values() else { return Ok(None) };
+        let (Some((sign1, f, a)), Some((sign2, g, b))) = (signed_trig(t1), signed_trig(t2)) else { return Ok(None) };
+        if f != g {
+            return Ok(None)
+        }
+
+        // With the positive term first, a - b is the only difference left.
+        let ((sign, _, a), (other, _, b)) = match sign1 < sign2 {
+            true => ((sign2, g, b), (sign1, f, a)),
+            false => ((sign1, f, a), (sign2, g, b)),
+        };
+        let half_plus = prod!(frac!(1, 2), sum!(a.clone(), b.clone()));
+        let half_minus = prod!(frac!(1, 2), sum!(a.clone(), neg!(b.clone())));
+        let value = match (f, sign == other) {
+            ("sin", true) => prod!(int!(2), func!("sin"; half_plus), func!("cos"; half_minus)),
+            ("sin", false) => prod!(int!(2), func!("cos"; half_plus), func!("sin"; half_minus)),
+            (_, true) => prod!(int!(2), func!("cos"; half_plus), func!("cos"; half_minus)),
+            (_, false) => prod!(int!(-2), func!("sin"; half_plus), func!("sin"; half_minus)),
+        };
+        Ok(Some(prod!(int!(sign), value)))
+    })
+}
+
+// Recognises `sin(a)`, `cos(a)` and their negatives.
+fn signed_trig(u: &Expression) -> Option<(i32, &str, &Expression)> {
+    match u {
+        Expression::Function(f @ (Function::Sin(a) | Function::Cos(a))) => Some((1, f.name(), a)),
+        Expression::Product(p) => match p.values() {
+            [c, Expression::Function(f @ (Function::Sin(a) | Function::Cos(a)))] if c == &int!(-1) => Some((-1, f.name(), a)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Writes the other trigonometric functions through sines and cosines.
+fn to_sin_cos(u: &Expression) -> Result<Expression, MathError> {
+    map(u, &|v| match v {
+        Expression::Function(Function::Tan(a)) => Ok(Some(div!(func!("sin"; *a.clone()), func!("cos"; *a.clone())))),
+        Expression::Function(Function::Cot(a)) => Ok(Some(div!(func!("cos"; *a.clone()), func!("sin"; *a.clone())))),
+        Expression::Function(Function::Sec(a)) => Ok(Some(inv!(func!("cos"; *a.clone())))),
+        Expression::Function(Function::Csc(a)) => Ok(Some(inv!(func!("sin"; *a.clone())))),
+        _ => Ok(None),
+    })?.simplify()
+}
+
+// Gathers the powers of sines and cosines in each product back into
+// tangents, cotangents, secants and cosecants.
+fn from_sin_cos(u: Expression) -> Result<Expression, MathError> {
+    map(&u, &|v| {
+        let Expression::Product(p) = v else { return Ok(None) };
+
+        let mut rest = Vec::new();
+        let mut angles: Vec<(Expression, i64, i64)> = Vec::new();
+        for c in p.values() {
+            let Some((name, a, n)) = sin_cos_power(c) else {
+                rest.push(c.clone());
+                continue
+            };
+            let k = match angles.iter().position(|(b, _, _)| b == &a) {
+                Some(k) => k,
+                None => {
+                    angles.push((a, 0, 0));
+                    angles.len() - 1
+                }
+            };
+            match name {
+                "sin" => angles[k].1 += n,
+                _ => angles[k].2 += n,
+            }
+        }
+
+        for (a, mut m, mut n) in angles {
+            let f = |name: &str, k: i64| pow!(func!(name; a.clone()), int!(k));
+            if m > 0 && n < 0 {
+                let k = m.min(-n);
+                rest.push(f("tan", k));
+                (m, n) = (m - k, n + k);
+            }
+            if m < 0 && n > 0 {
+                let k = (-m).min(n);
+                rest.push(f("cot", k));
+                (m, n) = (m + k, n - k);
+            }
+            rest.push(if m < 0 { f("csc", -m) } else { f("sin", m) });
+            rest.push(if n < 0 { f("sec", -n) } else { f("cos", n) });
+        }
+        Ok(Some(Product::new(rest).into()))
+    })
+}
+
+// Writes sines and cosines through `e^(i*a)` and `e^(-i*a)`, and the
+// hyperbolic functions through `e^a` and `e^(-a)`.
+fn to_exp(u: &Expression) -> Result<Option<Expression>, MathError> {
+    let Expression::Function(f) = u else { return Ok(None) };
+    let a = f.arg().clone();
+    let e = |w: Expression| func!("exp"; w);
+
+    let (plus, minus) = (e(prod!(imag!(), a.clone())), e(prod!(int!(-1), imag!(), a.clone())));
+    let sin = div!(sum!(plus.clone(), neg!(minus.clone())), prod!(int!(2), imag!()));
+    let cos = prod!(frac!(1, 2), sum!(plus, minus));
+
+    let (plus, minus) = (e(a.clone()), e(neg!(a)));
+    let sinh = prod!(frac!(1, 2), sum!(plus.clone(), neg!(minus.clone())));
+    let cosh = prod!(frac!(1, 2), sum!(plus, minus));
+
+    Ok(Some(match f {
+        Function::Sin(_) => sin,
+        Function::Cos(_) => cos,
+        Function::Tan(_) => div!(sin, cos),
+        Function::Cot(_) => div!(cos, sin),
+        Function::Sec(_) => inv!(cos),
+        Function::Csc(_) => inv!(sin),
+        Function::Sinh(_) => sinh,
+        Function::Cosh(_) => cosh,
+        Function::Tanh(_) => div!(sinh, cosh),
+        _ => return Ok(None),
+    }))
+}
+
+// The change of base `log(b, x) = ln(x)/ln(b)`, and the logarithmic forms
+// of the inverse functions, where the circular ones pass through i.
+fn to_ln(u: &Expression) -> Result<Option<Expression>, MathError> {
+    let Expression::Function(f) = u else { return Ok(None) };
+    let x = f.arg().clone();
+    let root = |c: i64| sqrt!(sum!(pow!(x.clone(), int!(2)), int!(c)));
+
+    Ok(Some(match f {
+        Function::Log(b, _) => div!(ln!(x), ln!(*b.clone())),
+        // -i ln(ix + sqrt(1 - x^2)) and -i ln(x + i sqrt(1 - x^2)).
+        Function::Asin(_) => prod!(int!(-1), imag!(), ln!(sum!(prod!(imag!(), x.clone()), sqrt!(sum!(int!(1), neg!(pow!(x, int!(2)))))))),
+        Function::Acos(_) => prod!(int!(-1), imag!(), ln!(sum!(x.clone(), prod!(imag!(), sqrt!(sum!(int!(1), neg!(pow!(x, int!(2))))))))),
+        // i/2 (ln(1 - ix) - ln(1 + ix)).
+        Function::Atan(_) => prod!(frac!(1, 2), imag!(), sum!(
+            ln!(sum!(int!(1), neg!(prod!(imag!(), x.clone())))),
+            neg!(ln!(sum!(int!(1), prod!(imag!(), x)))),
+        )),
+        Function::Asinh(_) => ln!(sum!(x.clone(), root(1))),
+        Function::Acosh(_) => ln!(sum!(x.clone(), root(-1))),
+        Function::Atanh(_) => prod!(frac!(1, 2), sum!(ln!(sum!(int!(1), x.clone())), neg!(ln!(sum!(int!(1), neg!(x)))))),
+        _ => return Ok(None),
+    }))
+}
+
+// Recognises `sin(a)^n` or `cos(a)^n` with any integer n.
+fn sin_cos_power(u: &Expression) -> Option<(&str, Expression, i64)> {
+    let (base, exp) = match u {
+        Expression::Power(p) => (p.base.as_ref(), p.exp.as_ref().clone()),
+        u => (u, int!(1)),
+    };
+    match (base, exp) {
+        (Expression::Function(f @ (Function::Sin(a) | Function::Cos(a))), Expression::Integer(n))
+            => Some((f.name(), *a.clone(), n.num().to_i64()?)),
+        _ => None,
+    }
+}
+
+// Euler's formula `e^(x + i*y) = e^x * (cos(y) + i*sin(y))`.
+fn from_exp(u: &Expression) -> Result<Option<Expression>, MathError> {
+    let Expression::Function(Function::Exp(w)) = u else { return Ok(None) };
+
+    match Complex::split(&w.clone().simplify()?)? {
+        Some(z) if z.im != int!(0) => Ok(Some(prod!(
+            func!("exp"; z.re),
+            sum!(func!("cos"; z.im.clone()), prod!(imag!(), func!("sin"; z.im))),
+        ))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn simplified(text: &str) -> Expression {
+        trigsimp(parse(text)).unwrap()
+    }
+
+    #[test]
+    fn pythagorean_identities() {
+        assert_eq!(simplified("sin(x)^2 + cos(x)^2"), int!(1));
+        assert_eq!(simplified("1 - sin(x)^2"), parse("cos(x)^2"));
+        assert_eq!(simplified("sec(x)^2 - tan(x)^2"), int!(1));
+        assert_eq!(simplified("cosh(x)^2 - sinh(x)^2"), int!(1));
+    }
+
+    #[test]
+    fn double_angles() {
+        assert_eq!(simplified("2 sin(x) cos(x)"), parse("sin(2x)"));
+        assert_eq!(simplified("cos(x)^2 - sin(x)^2"), parse("cos(2x)"));
+    }
+
+    #[test]
+    fn sum_and_difference_identities() {
+        assert_eq!(simplified("sin(x) cos(y) + cos(x) sin(y)"), parse("sin(x + y)"));
+        assert_eq!(simplified("sin(x) cos(y) - cos(x) sin(y)"), parse("sin(x - y)"));
+        assert_eq!(simplified("cos(x) cos(y) - sin(x) sin(y)"), parse("cos(x + y)"));
+        assert_eq!(simplified("cos(x) cos(y) + sin(x) sin(y)"), parse("cos(x - y)"));
+    }
+
+    #[test]
+    fn negated_angles_meet() {
+        assert_eq!(simplified("sin(y - x) + sin(x - y)"), int!(0));
+        assert_eq!(simplified("cos(y - x) - cos(x - y)"), int!(0));
+    }
+
+    #[test]
+    fn products_become_sums() {
+        assert_eq!(simplified("2 sin(x) cos(x)"), parse("sin(2x)"));
+        assert_eq!(simplified("2 cos(x + y) cos(x - y) - cos(2x)"), parse("cos(2y)"));
+        assert_eq!(simplified("2 sin(x + y) sin(x - y) + cos(2x)"), parse("cos(2y)"));
+    }
+
+    #[test]
+    fn quotients_become_tangents() {
+        assert_eq!(simplified("sin(x)/cos(x)"), parse("tan(x)"));
+        assert_eq!(simplified("cos(x)/sin(x)^2"), parse("cot(x) csc(x)"));
+    }
+
+    #[test]
+    fn rewriting_between_forms() {
+        assert_eq!(rewrite(parse("cos(x)"), Form::Exp).unwrap(), parse("(exp(i x) + exp(-i x))/2"));
+        assert_eq!(rewrite(parse("sinh(x)"), Form::Exp).unwrap(), parse("(exp(x) - exp(-x))/2"));
+        assert_eq!(rewrite(parse("exp(i x)"), Form::Trig).unwrap(), parse("cos(x) + i sin(x)"));
+        assert_eq!(rewrite(parse("log(2, x)"), Form::Ln).unwrap(), parse("ln(x)/ln(2)"));
+    }
+
+    #[test]
+    fn inverse_functions_as_logarithms() {
+        let ln = |text: &str| rewrite(parse(text), Form::Ln).unwrap();
+        assert_eq!(ln("asin(x)"), parse("-i ln(i x + sqrt(1 - x^2))"));
+        assert_eq!(ln("acos(x)"), parse("-i ln(x + i sqrt(1 - x^2))"));
+        assert_eq!(ln("atan(x)"), parse("i/2 (ln(1 - i x) - ln(1 + i x))"));
+        assert_eq!(ln("asinh(x)"), parse("ln(x + sqrt(x^2 + 1))"));
+        assert_eq!(ln("acosh(x)"), parse("ln(x + sqrt(x^2 - 1))"));
+        assert_eq!(ln("atanh(x)"), parse("(ln(1 + x) - ln(1 - x))/2"));
+    }
+}
@@ -59,6 +59,12 @@ macro_rules! var {
     };
 }
 
+macro_rules! imag {
+    () => {
+        Expression::Variable(Variable::new("i"))
+    };
+}
+
 macro_rules! sqrt {
     ( $x:expr ) => {
         Expression::Function(Function::Sqrt(Box::new($x)))
@@ -75,6 +81,9 @@ macro_rules! log {
     ( $x:expr ) => {
         Expression::Function(Function::new("log", $x))
     };
+    ( $b:expr, $x:expr ) => {
+        Expression::Function(Function::Log(Box::new($b), Box::new($x)))
+    };
 }
 
 macro_rules! ln {
@@ -96,6 +105,10 @@ mod sum;
 mod product;
 mod power;
 mod function;
+mod complex;
+mod equation;
+mod list;
+mod inequality;
 
 pub use integer::Integer;
 pub use rational::Rational;
@@ -103,4 +116,8 @@ pub use variable::Variable;
 pub use sum::Sum;
 pub use product::Product;
 pub use power::Power;
-pub use function::Function;
\ No newline at end of file
+pub use function::Function;
+pub use complex::Complex;
+pub use equation::Equation;
+pub use list::List;
+pub use inequality::{Inequality, Relation};
\ No newline at end of file
@@ -0,0 +1,200 @@
+use num_bigint::BigInt;
+use num_integer::Integer as _;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::types::{Integer, Rational, Variable, Sum, Product, Power};
+
+// Largest integer power of a symbolic complex value that `Complex::split`
+// multiplies out when separating its real and imaginary parts.
+const MAX_SPLIT_POWER: u32 = 16;
+
+// A view of an expression as `re + im*i`, where neither part contains `i`.
+// Every symbol other than `i` is treated as a real number.
+#[derive(Debug, Clone)]
+pub struct Complex {
+    pub re: Expression,
+    pub im: Expression,
+}
+
+impl Complex {
+    pub fn new(re: Expression, im: Expression) -> Complex {
+        Complex { re, im }
+    }
+
+    // Separates a simplified expression into simplified real and imaginary
+    // parts, or returns `None` when `i` appears somewhere the parts cannot
+    // be pulled apart, such as inside a function.
+    pub fn split(u: &Expression) -> Result<Option<Complex>, MathError> {
+        let z = match u {
+            u if u.is_imaginary_unit() => Complex::new(int!(0), int!(1)),
+
+            u if u.free_of(&imag!()) => Complex::new(u.clone(), int!(0)),
+
+            Expression::Sum(s) => {
+                let mut z = Complex::new(int!(0), int!(0));
+                for v in s.values() {
+                    let Some(w) = Complex::split(v)? else { return Ok(None) };
+                    z = z.add(w).simplify()?;
+                }
+                z
+            }
+
+            Expression::Product(p) => {
+                let mut z = Complex::new(int!(1), int!(0));
+                for v in p.values() {
+                    let Some(w) = Complex::split(v)? else { return Ok(None) };
+                    z = z.mul(w).simplify()?;
+                }
+                z
+            }
+
+            Expression::Power(p) => match p.exp.as_ref() {
+                Expression::Integer(n) if n.num().magnitude() <= &MAX_SPLIT_POWER.into() => {
+                    let Some(w) = Complex::split(&p.base)? else { return Ok(None) };
+                    w.pow(n.num())?
+                }
+                _ => return Ok(None),
+            },
+
+            _ => return Ok(None),
+        };
+
+        z.simplify().map(Some)
+    }
+
+    // Recognises a simplified Gaussian rational `a + b*i` with rational `a` and `b`.
+    pub fn gaussian(u: &Expression) -> Option<Complex> {
+        let numeric = |u: &Expression| matches!(u, Expression::Integer(_) | Expression::Rational(_));
+
+        match u {
+            u if numeric(u) => Some(Complex::new(u.clone(), int!(0))),
+
+            u if u.is_imaginary_unit() => Some(Complex::new(int!(0), int!(1))),
+
+            Expression::Product(p) => match p.values() {
+                [b, i] if numeric(b) && i.is_imaginary_unit() => Some(Complex::new(int!(0), b.clone())),
+                _ => None,
+            },
+
+            Expression::Sum(s) => match s.values() {
+                [a, bi] if numeric(a) => match Complex::gaussian(bi)? {
+                    Complex { re: Expression::Integer(r), im } if r.num().is_zero()
+                        => Some(Complex::new(a.clone(), im)),
+                    _ => None,
+                },
+                _ => None,
+            },
+
+            _ => None,
+        }
+    }
+
+    pub fn add(self, other: Complex) -> Complex {
+        Complex::new(sum!(self.re, other.re), sum!(self.im, other.im))
+    }
+
+    pub fn mul(self, other: Complex) -> Complex {
+        // (a + bi)(c + di) = (ac - bd) + (ad + bc)i
+        let (a, b, c, d) = (self.re, self.im, other.re, other.im);
+        Complex::new(
+            sum!(prod!(a.clone(), c.clone()), neg!(prod!(b.clone(), d.clone()))),
+            sum!(prod!(a, d), prod!(b, c)),
+        )
+    }
+
+    pub fn conj(self) -> Complex {
+        Complex::new(self.re, neg!(self.im))
+    }
+
+    pub fn recip(self) -> Result<Complex, MathError> {
+        // 1/(a + bi) = (a - bi)/(a^2 + b^2)
+        let norm = sum!(pow!(self.re.clone(), int!(2)), pow!(self.im.clone(), int!(2))).simplify()?;
+        if norm == int!(0) {
+            return Err(MathError::Undefined("Indeterminate form: k/0".to_string()))
+        }
+
+        let z = self.conj();
+        Complex::new(div!(z.re, norm.clone()), div!(z.im, norm)).simplify()
+    }
+
+    pub fn pow(self, n: &BigInt) -> Result<Complex, MathError> {
+        let Some(mut e) = n.magnitude().to_u32() else {
+            return Err(MathError::Overflow(format!("complex power with exponent {} is too large", n)))
+        };
+
+        let mut base = match n.is_negative() {
+            true => self.recip()?,
+            false => self.simplify()?,
+        };
+        let mut result = Complex::new(int!(1), int!(0));
+
+        while e > 0 {
+            if e.is_odd() {
+                result = result.mul(base.clone()).simplify()?;
+            }
+            e >>= 1;
+            if e > 0 {
+                base = base.clone().mul(base).simplify()?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn simplify(self) -> Result<Complex, MathError> {
+        Ok(Complex::new(self.re.simplify()?, self.im.simplify()?))
+    }
+
+    pub fn into_expression(self) -> Result<Expression, MathError> {
+        sum!(self.re, prod!(self.im, imag!())).simplify()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+    use crate::parser::{parse, try_parse};
+    use crate::settings;
+    use crate::types::{Function, Variable};
+
+    #[test]
+    fn the_imaginary_unit_squares_to_minus_one() {
+        assert_eq!(parse("i^2"), parse("-1"));
+        assert_eq!(parse("i^7"), parse("-i"));
+        assert_eq!(parse("(1 + i)(1 - i)"), parse("2"));
+        assert_eq!(parse("1/(1 + i)"), parse("1/2 - i/2"));
+        assert_eq!(parse("(2 + 3i)^2"), parse("-5 + 12i"));
+    }
+
+    #[test]
+    fn even_roots_of_negatives_need_complex_mode() {
+        assert!(try_parse("sqrt(-4)").is_err());
+        settings::set_complex_mode(true);
+        assert_eq!(parse("sqrt(-4)"), parse("2i"));
+    }
+
+    #[test]
+    fn parts_of_complex_numbers() {
+        settings::set_complex_mode(true);
+        assert_eq!(parse("re(3 + 2i)"), parse("3"));
+        assert_eq!(parse("im(3 + 2i)"), parse("2"));
+        assert_eq!(parse("conj(3 + 2i)"), parse("3 - 2i"));
+        assert_eq!(parse("|3 + 4i|"), parse("5"));
+        assert_eq!(parse("arg(1 + i)"), parse("pi/4"));
+    }
+
+    #[test]
+    fn parts_of_symbols_need_them_to_be_real() {
+        settings::set_complex_mode(true);
+        assert_eq!(parse("re(x)"), func!("re"; var!("x")));
+        assert_eq!(parse("im(x)"), func!("im"; var!("x")));
+        assert_eq!(parse("conj(x)"), func!("conj"; var!("x")));
+        assert_eq!(parse("|x + i|"), func!("abs"; parse("x + i")));
+
+        settings::assume("x", settings::Property::Real);
+        assert_eq!(parse("re(x + 2i)"), parse("x"));
+        assert_eq!(parse("im(x + 2i)"), parse("2"));
+        assert_eq!(parse("conj(x + 2i)"), parse("x - 2i"));
+    }
+}
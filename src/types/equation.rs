@@ -0,0 +1,50 @@
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+use crate::types::{Integer, Product, Sum};
+
+// A statement `lhs = rhs`, which is only simplified side by side.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Equation {
+    pub lhs: Box<Expression>,
+    pub rhs: Box<Expression>,
+}
+
+impl Simplify for Equation {
+    fn simplify(self) -> Result<Expression, MathError> {
+        Ok(Equation::new(self.lhs.simplify()?, self.rhs.simplify()?).into())
+    }
+}
+
+impl Evaluate for Equation {
+    fn evaluate(&self, _prec: u64) -> Result<Float, MathError> {
+        Err(MathError::Undefined("Cannot evaluate an equation".to_string()))
+    }
+}
+
+impl Expand for Equation {
+    fn expand(self) -> Result<Expression, MathError> {
+        Ok(Equation::new(self.lhs.expand()?, self.rhs.expand()?).into())
+    }
+}
+
+impl Differentiate for Equation {
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        Ok(Equation::new(self.lhs.derivative(x)?, self.rhs.derivative(x)?).into())
+    }
+}
+
+impl Equation {
+    pub fn new(lhs: Expression, rhs: Expression) -> Equation {
+        Equation {
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    // The equivalent expression `lhs - rhs`, which is zero exactly when
+    // the equation holds.
+    pub fn zero_form(&self) -> Result<Expression, MathError> {
+        sum!(*self.lhs.clone(), neg!(*self.rhs.clone())).simplify()
+    }
+}
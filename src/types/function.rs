@@ -1,29 +1,247 @@
 use std::rc::Rc;
-use std::cmp;
+use std::cmp::{self, Ordering};
 use std::fmt;
 
+use num_bigint::BigInt;
+use num_integer::{Integer as _, Roots};
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
 use crate::expression::Expression;
-use crate::expression::UndefinedError;
-use crate::traits::Simplify;
-use crate::types::{Power, Rational, Product, Sum, Integer, Variable};
+use crate::expression::MathError;
+use crate::float::Float;
+use crate::polynomial::Polynomial;
+use crate::settings;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+use crate::types::{Power, Rational, Product, Sum, Integer, Variable, Complex};
+
+// Bits of precision used to recognise the known values of inverse functions.
+const MATCH_PRECISION: u64 = 128;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Function {
     Sqrt(Box<Expression>),
     Cbrt(Box<Expression>),
-    Log(Box<Expression>),
+    Abs(Box<Expression>),
+    // The value comes first, as in `root(x, 5)`.
+    Root(Box<Expression>, Box<Expression>),
+    // The base comes first, as in `log(2, x)`.
+    Log(Box<Expression>, Box<Expression>),
     Ln(Box<Expression>),
-    Other(Box<str>, Box<Expression>)
+    Exp(Box<Expression>),
+    Re(Box<Expression>),
+    Im(Box<Expression>),
+    Conj(Box<Expression>),
+    Arg(Box<Expression>),
+    Sin(Box<Expression>),
+    Cos(Box<Expression>),
+    Tan(Box<Expression>),
+    Sec(Box<Expression>),
+    Csc(Box<Expression>),
+    Cot(Box<Expression>),
+    Asin(Box<Expression>),
+    Acos(Box<Expression>),
+    Atan(Box<Expression>),
+    Sinh(Box<Expression>),
+    Cosh(Box<Expression>),
+    Tanh(Box<Expression>),
+    Asinh(Box<Expression>),
+    Acosh(Box<Expression>),
+    Atanh(Box<Expression>),
+    Max(Vec<Expression>),
+    Min(Vec<Expression>),
+    Gcd(Vec<Expression>),
+    Lcm(Vec<Expression>),
+    Order(Box<Expression>),
+    Other(Box<str>, Vec<Expression>)
 }
 
 impl Simplify for Function {
-    fn simplify(self) -> Result<Expression, UndefinedError> {
+    fn simplify(self) -> Result<Expression, MathError> {
         match self {
             Function::Sqrt(u) => Function::simplify_sqrt(u.simplify()?),
             Function::Cbrt(u) => Function::simplify_cbrt(u.simplify()?),
-            Function::Log(u) => Function::simplify_log(u.simplify()?),
+            Function::Abs(u) => Function::simplify_abs(u.simplify()?),
+            Function::Root(u, n) => pow!(*u, inv!(*n)).simplify(),
+            Function::Log(b, u) => Function::simplify_log(b.simplify()?, u.simplify()?),
             Function::Ln(u) => Function::simplify_ln(u.simplify()?),
-            Function::Other(name, u) => Ok(func!(name; u.simplify()?))
+            Function::Exp(u) => Function::simplify_exp(u.simplify()?),
+            Function::Re(u) => Function::simplify_re(u.simplify()?),
+            Function::Im(u) => Function::simplify_im(u.simplify()?),
+            Function::Conj(u) => Function::simplify_conj(u.simplify()?),
+            Function::Arg(u) => Function::simplify_arg(u.simplify()?),
+            f @ (Function::Sin(_)
+            | Function::Cos(_)
+            | Function::Tan(_)
+            | Function::Sec(_)
+            | Function::Csc(_)
+            | Function::Cot(_)) => Function::simplify_trig(f.name(), f.arg().clone().simplify()?),
+            f @ (Function::Sinh(_)
+            | Function::Cosh(_)
+            | Function::Tanh(_)) => Function::simplify_hyperbolic(f.name(), f.arg().clone().simplify()?),
+            f @ (Function::Asin(_)
+            | Function::Acos(_)
+            | Function::Atan(_)
+            | Function::Asinh(_)
+            | Function::Acosh(_)
+            | Function::Atanh(_)) => Function::simplify_inverse(f.name(), f.arg().clone().simplify()?),
+            f @ (Function::Max(_) | Function::Min(_)) => Function::simplify_extremum(f),
+            f @ (Function::Gcd(_) | Function::Lcm(_)) => Function::simplify_gcd(f),
+            Function::Order(u) => Function::simplify_order(u.simplify()?),
+            f @ Function::Other(..) => Ok(Expression::Function(f.map(|u| u.clone().simplify())?)),
+        }
+    }
+}
+
+impl Expand for Function {
+    // The argument of an order term stays a power of the expansion variable.
+    fn expand(self) -> Result<Expression, MathError> {
+        match self {
+            Function::Order(_) => self.simplify(),
+            _ => self.map(|u| u.clone().expand())?.simplify(),
+        }
+    }
+}
+
+impl Differentiate for Function {
+    // The chain rule, with every symbol treated as real so that the
+    // derivative passes through `re`, `im` and `conj`.
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        if self.args().iter().all(|u| u.free_of(x)) {
+            return Ok(int!(0))
+        }
+        match self {
+            // A base depending on x is changed to e first.
+            Function::Log(b, u) if !b.free_of(x)
+                => return div!(ln!(*u.clone()), ln!(*b.clone())).derivative(x),
+            Function::Root(u, n)
+                => return pow!(*u.clone(), inv!(*n.clone())).derivative(x),
+            Function::Max(_) | Function::Min(_) | Function::Gcd(_) | Function::Lcm(_)
+                => return Err(MathError::Undefined(format!("Cannot differentiate `{}`", self))),
+            _ => (),
+        }
+
+        let u = self.arg();
+        let du = u.derivative(x)?;
+
+        match self {
+            Function::Sqrt(u)
+                => prod!(frac!(1, 2), du, inv!(sqrt!(*u.clone()))).simplify(),
+            Function::Cbrt(u)
+                => prod!(frac!(1, 3), du, pow!(*u.clone(), frac!(-2, 3))).simplify(),
+            Function::Abs(u)
+                => prod!(du, *u.clone(), inv!(func!("abs"; *u.clone()))).simplify(),
+            Function::Log(b, u)
+                => prod!(du, inv!(prod!(*u.clone(), ln!(*b.clone())))).simplify(),
+            Function::Ln(u)
+                => prod!(du, inv!(*u.clone())).simplify(),
+            Function::Exp(u)
+                => prod!(du, func!("exp"; *u.clone())).simplify(),
+            Function::Sin(u)
+                => prod!(du, func!("cos"; *u.clone())).simplify(),
+            Function::Cos(u)
+                => prod!(int!(-1), du, func!("sin"; *u.clone())).simplify(),
+            Function::Tan(u)
+                => prod!(du, pow!(func!("sec"; *u.clone()), int!(2))).simplify(),
+            Function::Sec(u)
+                => prod!(du, func!("sec"; *u.clone()), func!("tan"; *u.clone())).simplify(),
+            Function::Csc(u)
+                => prod!(int!(-1), du, func!("csc"; *u.clone()), func!("cot"; *u.clone())).simplify(),
+            Function::Cot(u)
+                => prod!(int!(-1), du, pow!(func!("csc"; *u.clone()), int!(2))).simplify(),
+            Function::Asin(u)
+                => prod!(du, inv!(sqrt!(sum!(int!(1), neg!(pow!(*u.clone(), int!(2))))))).simplify(),
+            Function::Acos(u)
+                => prod!(int!(-1), du, inv!(sqrt!(sum!(int!(1), neg!(pow!(*u.clone(), int!(2))))))).simplify(),
+            Function::Atan(u)
+                => prod!(du, inv!(sum!(int!(1), pow!(*u.clone(), int!(2))))).simplify(),
+            Function::Sinh(u)
+                => prod!(du, func!("cosh"; *u.clone())).simplify(),
+            Function::Cosh(u)
+                => prod!(du, func!("sinh"; *u.clone())).simplify(),
+            Function::Tanh(u)
+                => prod!(du, pow!(func!("cosh"; *u.clone()), int!(-2))).simplify(),
+            Function::Asinh(u)
+                => prod!(du, inv!(sqrt!(sum!(pow!(*u.clone(), int!(2)), int!(1))))).simplify(),
+            Function::Acosh(u)
+                => prod!(du, inv!(sqrt!(sum!(pow!(*u.clone(), int!(2)), int!(-1))))).simplify(),
+            Function::Atanh(u)
+                => prod!(du, inv!(sum!(int!(1), neg!(pow!(*u.clone(), int!(2)))))).simplify(),
+            Function::Re(_) | Function::Im(_) | Function::Conj(_) | Function::Order(_)
+                => func!(self.name(); du).simplify(),
+            Function::Arg(_)
+            | Function::Root(..)
+            | Function::Max(_)
+            | Function::Min(_)
+            | Function::Gcd(_)
+            | Function::Lcm(_)
+            | Function::Other(..)
+                => Err(MathError::Undefined(format!("Cannot differentiate `{}`", self))),
+        }
+    }
+}
+
+impl Evaluate for Function {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        match self {
+            Function::Sqrt(u) => u.evaluate(prec)?.sqrt(prec),
+            Function::Cbrt(u) => {
+                let x = u.evaluate(prec)?;
+                let root = x.abs().pow(&Float::from_ratio(&1.into(), &3.into(), prec)?, prec)?;
+                Ok(if x.is_negative() { -root } else { root })
+            }
+            Function::Abs(u) => Ok(u.evaluate(prec)?.abs()),
+            Function::Root(u, n) => pow!(*u.clone(), inv!(*n.clone())).evaluate(prec),
+            Function::Log(b, u) => u.evaluate(prec)?.ln(prec)?.div(&b.evaluate(prec)?.ln(prec)?, prec),
+            Function::Ln(u) => u.evaluate(prec)?.ln(prec),
+            Function::Exp(u) => u.evaluate(prec)?.exp(prec),
+            Function::Re(u) => Function::evaluate_parts(u, prec).map(|(re, _)| re),
+            Function::Im(u) => Function::evaluate_parts(u, prec).map(|(_, im)| im),
+            Function::Conj(u) => match Function::evaluate_parts(u, prec)? {
+                (re, im) if im.is_zero() => Ok(re),
+                _ => Err(MathError::Undefined("Cannot evaluate a complex value as a real number".to_string())),
+            },
+            Function::Arg(u) => {
+                let (re, im) = Function::evaluate_parts(u, prec)?;
+                Float::atan2(&im, &re, prec)
+            }
+            Function::Sin(u) => Function::evaluate_angle(u, prec)?.sin(prec),
+            Function::Cos(u) => Function::evaluate_angle(u, prec)?.cos(prec),
+            Function::Tan(u) => {
+                let x = Function::evaluate_angle(u, prec)?;
+                x.sin(prec)?.div(&x.cos(prec)?, prec)
+            }
+            Function::Sec(u) => Float::from_int(1).div(&Function::evaluate_angle(u, prec)?.cos(prec)?, prec),
+            Function::Csc(u) => Float::from_int(1).div(&Function::evaluate_angle(u, prec)?.sin(prec)?, prec),
+            Function::Cot(u) => {
+                let x = Function::evaluate_angle(u, prec)?;
+                x.cos(prec)?.div(&x.sin(prec)?, prec)
+            }
+            Function::Asin(u) => u.evaluate(prec)?.asin(prec),
+            Function::Acos(u) => u.evaluate(prec)?.acos(prec),
+            Function::Atan(u) => u.evaluate(prec)?.atan(prec),
+            Function::Sinh(u) => u.evaluate(prec)?.sinh(prec),
+            Function::Cosh(u) => u.evaluate(prec)?.cosh(prec),
+            Function::Tanh(u) => u.evaluate(prec)?.tanh(prec),
+            Function::Asinh(u) => u.evaluate(prec)?.asinh(prec),
+            Function::Acosh(u) => u.evaluate(prec)?.acosh(prec),
+            Function::Atanh(u) => u.evaluate(prec)?.atanh(prec),
+            Function::Max(args) | Function::Min(args) => {
+                let values = args.iter().map(|u| u.evaluate(prec)).collect::<Result<Vec<_>, _>>()?;
+                let extremum = match self {
+                    Function::Max(_) => values.into_iter().max(),
+                    _ => values.into_iter().min(),
+                };
+                extremum.ok_or_else(|| MathError::Undefined(format!("Cannot evaluate `{}`", self)))
+            }
+            Function::Gcd(_) | Function::Lcm(_) => match self.clone().simplify()? {
+                Expression::Function(_) => Err(MathError::Undefined(format!("Cannot evaluate `{}` of non-integers", self.name()))),
+                v => v.evaluate(prec),
+            },
+            Function::Order(_)
+                => Err(MathError::Undefined("Cannot evaluate an order term".to_string())),
+            Function::Other(name, _)
+                => Err(MathError::Undefined(format!("Cannot evaluate unknown function `{}`", name)))
         }
     }
 }
@@ -33,13 +251,193 @@ impl Function {
         match name.into().as_str() {
             "sqrt" => Function::Sqrt(Box::new(arg)),
             "cbrt" => Function::Cbrt(Box::new(arg)),
-            "log" => Function::Log(Box::new(arg)),
+            "abs" => Function::Abs(Box::new(arg)),
+            "log" => Function::Log(Box::new(int!(10)), Box::new(arg)),
             "ln" => Function::Ln(Box::new(arg)),
-            name => Function::Other(Box::from(name), Box::new(arg))
+            "exp" => Function::Exp(Box::new(arg)),
+            "re" => Function::Re(Box::new(arg)),
+            "im" => Function::Im(Box::new(arg)),
+            "conj" => Function::Conj(Box::new(arg)),
+            "arg" => Function::Arg(Box::new(arg)),
+            "sin" => Function::Sin(Box::new(arg)),
+            "cos" => Function::Cos(Box::new(arg)),
+            "tan" => Function::Tan(Box::new(arg)),
+            "sec" => Function::Sec(Box::new(arg)),
+            "csc" => Function::Csc(Box::new(arg)),
+            "cot" => Function::Cot(Box::new(arg)),
+            "asin" => Function::Asin(Box::new(arg)),
+            "acos" => Function::Acos(Box::new(arg)),
+            "atan" => Function::Atan(Box::new(arg)),
+            "sinh" => Function::Sinh(Box::new(arg)),
+            "cosh" => Function::Cosh(Box::new(arg)),
+            "tanh" => Function::Tanh(Box::new(arg)),
+            "asinh" => Function::Asinh(Box::new(arg)),
+            "acosh" => Function::Acosh(Box::new(arg)),
+            "atanh" => Function::Atanh(Box::new(arg)),
+            "max" => Function::Max(vec![arg]),
+            "min" => Function::Min(vec![arg]),
+            "gcd" => Function::Gcd(vec![arg]),
+            "lcm" => Function::Lcm(vec![arg]),
+            "O" => Function::Order(Box::new(arg)),
+            name => Function::Other(Box::from(name), vec![arg])
+        }
+    }
+
+    // Builds a call from its list of arguments, checking how many of them
+    // the function takes.
+    pub fn from_args(name: &str, mut args: Vec<Expression>) -> Result<Function, String> {
+        match (name, args.len()) {
+            (_, 0) => Err(format!("`{}` expects at least one argument", name)),
+            ("log", 2) => Ok(Function::Log(Box::new(args.remove(0)), Box::new(args.remove(0)))),
+            ("log", 3..) => Err("log expects a value, or a base and a value".to_string()),
+            ("root", 2) => Ok(Function::Root(Box::new(args.remove(0)), Box::new(args.remove(0)))),
+            ("root", _) => Err("root expects a value and a degree".to_string()),
+            ("max", _) => Ok(Function::Max(args)),
+            ("min", _) => Ok(Function::Min(args)),
+            ("gcd", _) => Ok(Function::Gcd(args)),
+            ("lcm", _) => Ok(Function::Lcm(args)),
+            (_, 1) => Ok(Function::new(name, args.remove(0))),
+            _ if !matches!(Function::new(name, int!(0)), Function::Other(..))
+                => Err(format!("`{}` expects a single argument", name)),
+            _ => Ok(Function::Other(Box::from(name), args)),
+        }
+    }
+
+    // The same function applied to new arguments, of which there are as
+    // many as before.
+    pub fn with_args(&self, mut args: Vec<Expression>) -> Function {
+        match self {
+            Function::Log(..) => Function::Log(Box::new(args.remove(0)), Box::new(args.remove(0))),
+            Function::Root(..) => Function::Root(Box::new(args.remove(0)), Box::new(args.remove(0))),
+            Function::Max(_) => Function::Max(args),
+            Function::Min(_) => Function::Min(args),
+            Function::Gcd(_) => Function::Gcd(args),
+            Function::Lcm(_) => Function::Lcm(args),
+            Function::Other(name, _) => Function::Other(name.clone(), args),
+            _ => Function::new(self.name(), args.remove(0)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Function::Sqrt(_) => "sqrt",
+            Function::Cbrt(_) => "cbrt",
+            Function::Abs(_) => "abs",
+            Function::Root(..) => "root",
+            Function::Log(..) => "log",
+            Function::Ln(_) => "ln",
+            Function::Exp(_) => "exp",
+            Function::Re(_) => "re",
+            Function::Im(_) => "im",
+            Function::Conj(_) => "conj",
+            Function::Arg(_) => "arg",
+            Function::Sin(_) => "sin",
+            Function::Cos(_) => "cos",
+            Function::Tan(_) => "tan",
+            Function::Sec(_) => "sec",
+            Function::Csc(_) => "csc",
+            Function::Cot(_) => "cot",
+            Function::Asin(_) => "asin",
+            Function::Acos(_) => "acos",
+            Function::Atan(_) => "atan",
+            Function::Sinh(_) => "sinh",
+            Function::Cosh(_) => "cosh",
+            Function::Tanh(_) => "tanh",
+            Function::Asinh(_) => "asinh",
+            Function::Acosh(_) => "acosh",
+            Function::Atanh(_) => "atanh",
+            Function::Max(_) => "max",
+            Function::Min(_) => "min",
+            Function::Gcd(_) => "gcd",
+            Function::Lcm(_) => "lcm",
+            Function::Order(_) => "O",
+            Function::Other(name, _) => name,
+        }
+    }
+
+    // The argument of a function of one value, the value rather than the
+    // base or degree of a logarithm or root, and otherwise the first argument.
+    pub fn arg(&self) -> &Expression {
+        match self {
+            Function::Sqrt(u)
+            | Function::Cbrt(u)
+            | Function::Abs(u)
+            | Function::Root(u, _)
+            | Function::Log(_, u)
+            | Function::Ln(u)
+            | Function::Exp(u)
+            | Function::Re(u)
+            | Function::Im(u)
+            | Function::Conj(u)
+            | Function::Arg(u)
+            | Function::Sin(u)
+            | Function::Cos(u)
+            | Function::Tan(u)
+            | Function::Sec(u)
+            | Function::Csc(u)
+            | Function::Cot(u)
+            | Function::Asin(u)
+            | Function::Acos(u)
+            | Function::Atan(u)
+            | Function::Sinh(u)
+            | Function::Cosh(u)
+            | Function::Tanh(u)
+            | Function::Asinh(u)
+            | Function::Acosh(u)
+            | Function::Atanh(u)
+            | Function::Order(u) => u,
+            Function::Max(args)
+            | Function::Min(args)
+            | Function::Gcd(args)
+            | Function::Lcm(args)
+            | Function::Other(_, args) => &args[0],
+        }
+    }
+
+    // Every argument in order, as they are written.
+    pub fn args(&self) -> Vec<&Expression> {
+        match self {
+            Function::Log(b, u) => vec![b, u],
+            Function::Root(u, n) => vec![u, n],
+            Function::Max(args)
+            | Function::Min(args)
+            | Function::Gcd(args)
+            | Function::Lcm(args)
+            | Function::Other(_, args) => args.iter().collect(),
+            _ => vec![self.arg()],
         }
     }
 
-    fn simplify_sqrt(u: Expression) -> Result<Expression, UndefinedError> {
+    // Rebuilds the same function from f applied to each of its arguments.
+    pub fn map(&self, f: impl FnMut(&Expression) -> Result<Expression, MathError>) -> Result<Function, MathError> {
+        Ok(self.with_args(self.args().into_iter().map(f).collect::<Result<_, _>>()?))
+    }
+
+    // `sqrt(x^2) = |x|` for real x, which takes every factor that is an
+    // even power out of the root, as in `sqrt(4x^2) = 2|x|`.
+    fn simplify_sqrt(u: Expression) -> Result<Expression, MathError> {
+        let factors = match &u {
+            Expression::Product(p) => p.values(),
+            u => std::slice::from_ref(u),
+        };
+
+        let mut outside = Vec::new();
+        let mut inside = Vec::new();
+        for v in factors {
+            match v {
+                Expression::Power(p) if Function::is_real(&p.base)? => match p.exp.as_ref() {
+                    Expression::Integer(n) if n.num().is_even()
+                        => outside.push(pow!(func!("abs"; *p.base.clone()), int!(n.num() / 2))),
+                    _ => inside.push(v.clone()),
+                },
+                v => inside.push(v.clone()),
+            }
+        }
+        if !outside.is_empty() {
+            outside.push(sqrt!(Product::new(inside).into()));
+            return Product::new(outside).simplify()
+        }
+
         match pow!(u, frac!(1, 2)).simplify()? {
             Expression::Power(p) if p.exp.as_ref() == &frac!(1, 2)
                 => Ok(sqrt!(*p.base)),
@@ -47,7 +445,39 @@ impl Function {
         }
     }
 
-    fn simplify_cbrt(u: Expression) -> Result<Expression, UndefinedError> {
+    // The absolute value splits over products and integer powers, and is
+    // decided outright for real numbers and for complex ones in complex mode.
+    fn simplify_abs(u: Expression) -> Result<Expression, MathError> {
+        match u {
+            Expression::Product(p)
+                => Product::new(p.values().iter().map(|v| func!("abs"; v.clone())).collect()).simplify(),
+
+            Expression::Power(p) if matches!(p.exp.as_ref(), Expression::Integer(_))
+                => pow!(func!("abs"; *p.base), *p.exp).simplify(),
+
+            Expression::Function(f @ (Function::Abs(_) | Function::Exp(_)))
+                => Ok(Expression::Function(f)),
+
+            u if u.sign() == Some(Ordering::Less)
+                => neg!(u).expand(),
+
+            u if u.is_nonnegative()
+                => Ok(u),
+
+            u if Function::is_negated(&u)
+                => func!("abs"; neg!(u).expand()?).simplify(),
+
+            u if settings::complex_mode() => match Function::real_parts(&u)? {
+                Some(z) if z.im != int!(0)
+                    => sqrt!(sum!(pow!(z.re, int!(2)), pow!(z.im, int!(2)))).simplify(),
+                _ => Ok(func!("abs"; u)),
+            },
+
+            u => Ok(func!("abs"; u)),
+        }
+    }
+
+    fn simplify_cbrt(u: Expression) -> Result<Expression, MathError> {
         match pow!(u, frac!(1, 3)).simplify()? {
             Expression::Power(p) if p.exp.as_ref() == &frac!(1, 3)
                 => Ok(cbrt!(*p.base)),
@@ -55,62 +485,656 @@ impl Function {
         }
     }
 
-    fn simplify_log(u: Expression) -> Result<Expression, UndefinedError> {
-        match u {
-            Expression::Power(p)
-                => prod!(*p.exp, log!(*p.base)).simplify(),
+    // The logarithm of u to the base b. A rational u that is a rational
+    // power of a rational b gives its exponent, as in `log(4, 8) = 3/2`,
+    // while powers, radicals, products and quotients are split up.
+    fn simplify_log(b: Expression, u: Expression) -> Result<Expression, MathError> {
+        match &b {
+            Expression::Variable(v) if v.as_str() == "e"
+                => return Function::simplify_ln(u),
+            Expression::Integer(_) | Expression::Rational(_) if b.sign() != Some(Ordering::Greater) || b == int!(1)
+                => return Err(MathError::Undefined(format!("Invalid logarithm base `{}`", b))),
+            // `log(b^c, u) = log(b, u)/c`
+            Expression::Power(p) if matches!(p.exp.as_ref(), Expression::Integer(_) | Expression::Rational(_)) => {
+                if let Some(x) = Function::log_power_base(&p.base, &p.exp)? {
+                    return div!(log!(x, u), *p.exp.clone()).simplify()
+                }
+            }
+            Expression::Function(Function::Sqrt(a))
+                => return prod!(int!(2), log!(*a.clone(), u)).simplify(),
+            Expression::Function(Function::Cbrt(a))
+                => return prod!(int!(3), log!(*a.clone(), u)).simplify(),
+            _ => (),
+        }
 
-            Expression::Product(p)
-                => Sum::new(p.values().iter().map(|e| log!(e.clone())).collect()).simplify(),
+        if u == b {
+            return Ok(int!(1))
+        }
+        if let (Some(x), Some(y)) = (Polynomial::rational(&b), Polynomial::rational(&u)) {
+            if let Some(q) = Function::exact_log(&x, &y) {
+                return Ok(Polynomial::number(&q))
+            }
+        }
+
+        if let Some(v) = Function::split_log(&u, |v| log!(b.clone(), v))? {
+            return v.simplify()
+        }
 
+        match u {
             Expression::Rational(r)
-                => sum!(log!(int!(r.num())), neg!(log!(int!(r.den())))).simplify(),
+                => sum!(log!(b.clone(), int!(r.num().clone())), neg!(log!(b, int!(r.den().clone())))).simplify(),
+
+            Expression::Variable(v) if v.as_str() == "e"
+                => inv!(ln!(b)).simplify(),
+
+            Expression::Function(Function::Exp(x)) if Function::is_real(&x)?
+                => div!(*x, ln!(b)).simplify(),
 
-            Expression::Integer(n) if n.num() <= 0
-                => Err(UndefinedError("negative or zero logarithm".to_string())),
+            Expression::Integer(n) if n.num().is_negative() && settings::complex_mode()
+                => sum!(log!(b.clone(), int!(-n.num())), div!(prod!(imag!(), var!("pi")), ln!(b))).simplify(),
+
+            Expression::Integer(n) if !n.num().is_positive()
+                => Err(MathError::Undefined("negative or zero logarithm".to_string())),
+
+            u => Ok(log!(b, u)),
+        }
+    }
 
-            Expression::Integer(n) => {
-                let mut power_of_ten = 1;
-                let mut exp = 0;
-                while power_of_ten < n.num() {
-                    power_of_ten *= 10;
-                    exp += 1;
+    // Splits the logarithm of a product, power or radical into a sum of
+    // logarithms where this holds for every value the variables may take.
+    // Positive factors come out of a product, as in `ln(2x) = ln(2) + ln(x)`,
+    // while `ln(x*y)` stays whole unless x or y is known to be positive.
+    fn split_log(u: &Expression, log: impl Fn(Expression) -> Expression) -> Result<Option<Expression>, MathError> {
+        match u {
+            Expression::Product(p) => {
+                let mut terms = Vec::new();
+                let mut rest = Vec::new();
+                for v in p.values() {
+                    match v {
+                        v if v.is_positive() => terms.push(log(v.clone())),
+                        // `ln(-c*x) = ln(c) + ln(-x)` for a positive number c.
+                        Expression::Integer(_) | Expression::Rational(_) if v != &int!(-1) => {
+                            terms.push(log(neg!(v.clone())));
+                            rest.push(int!(-1));
+                        }
+                        v => rest.push(v.clone()),
+                    }
                 }
-                if power_of_ten == n.num() {
-                    Ok(int!(exp))
+                if terms.is_empty() {
+                    return Ok(None)
                 }
-                else {
-                    Ok(ln!(n.into()))
+                if !rest.is_empty() {
+                    terms.push(log(Product::new(rest).into()));
                 }
-            },
+                Ok(Some(Sum::new(terms).into()))
+            }
+            Expression::Power(p)
+                => Ok(Function::log_power_base(&p.base, &p.exp)?.map(|x| prod!(*p.exp.clone(), log(x)))),
+            Expression::Function(Function::Sqrt(x))
+                => Ok(Some(prod!(frac!(1, 2), log(*x.clone())))),
+            Expression::Function(Function::Cbrt(x))
+                => Ok(Function::log_power_base(x, &frac!(1, 3))?.map(|x| prod!(frac!(1, 3), log(x)))),
+            _ => Ok(None),
+        }
+    }
+
+    // The x with `ln(b^c) = c*ln(x)`, which is b itself when b is positive
+    // and `|b|` when an even power hides the sign of a real b. In real mode
+    // the logarithm only exists where b^c is positive, which is enough for
+    // any other power.
+    fn log_power_base(b: &Expression, c: &Expression) -> Result<Option<Expression>, MathError> {
+        let real_mode = !settings::complex_mode();
+        let even = match c {
+            Expression::Integer(n) => !n.num().bit(0),
+            Expression::Rational(q) => real_mode && !q.num().bit(0),
+            _ => false,
+        };
 
-            u => Ok(log!(u)),
+        if b.is_positive() {
+            Ok(Some(b.clone()))
+        }
+        else if even && Function::is_real(b)? {
+            Ok(Some(func!("abs"; b.clone())))
+        }
+        else if real_mode {
+            Ok(Some(b.clone()))
+        }
+        else {
+            Ok(None)
         }
     }
 
-    fn simplify_ln(u: Expression) -> Result<Expression, UndefinedError> {
-        match u {
-            Expression::Power(p)
-                => prod!(*p.exp, ln!(*p.base)).simplify(),
+    // The rational q with `b^q = u`, found by writing the positive
+    // rationals b and u as powers of the same rational or its reciprocal.
+    fn exact_log(b: &BigRational, u: &BigRational) -> Option<BigRational> {
+        if u.is_one() {
+            return Some(BigRational::zero())
+        }
+        if !b.is_positive() || !u.is_positive() || b.is_one() {
+            return None
+        }
 
-            Expression::Product(p)
-                => Sum::new(p.values().iter().map(|e| ln!(e.clone())).collect()).simplify(),
+        let (c, m) = Function::perfect_power(b);
+        let (d, n) = Function::perfect_power(u);
+        let q = BigRational::new(n.into(), m.into());
+        match d {
+            d if d == c => Some(q),
+            d if d == c.recip() => Some(-q),
+            _ => None,
+        }
+    }
 
-                Expression::Rational(r)
-                => sum!(ln!(int!(r.num())), neg!(ln!(int!(r.den())))).simplify(),
+    // Writes a positive rational other than 1 as `c^k` with k as large as
+    // possible.
+    fn perfect_power(r: &BigRational) -> (BigRational, u32) {
+        let bits = cmp::max(r.numer().bits(), r.denom().bits()) as u32;
+        for k in (2..=bits).rev() {
+            let (a, c) = (r.numer().nth_root(k), r.denom().nth_root(k));
+            if &a.pow(k) == r.numer() && &c.pow(k) == r.denom() {
+                return (BigRational::new(a, c), k)
+            }
+        }
+        (r.clone(), 1)
+    }
+
+    fn simplify_ln(u: Expression) -> Result<Expression, MathError> {
+        if let Some(v) = Function::split_log(&u, |v| ln!(v))? {
+            return v.simplify()
+        }
+
+        match u {
+            Expression::Rational(r)
+                => sum!(ln!(int!(r.num().clone())), neg!(ln!(int!(r.den().clone())))).simplify(),
 
             Expression::Variable(v) if v.as_str() == "e"
                 => Ok(int!(1)),
 
-            Expression::Integer(n) if n.num() == 1
+            // ln(exp(x)) = x holds on the principal branch, which every real x is on.
+            Expression::Function(Function::Exp(x)) if Function::is_real(&x)?
+                => Ok(*x),
+
+            u if u.is_imaginary_unit()
+                => prod!(frac!(1, 2), var!("pi"), imag!()).simplify(),
+
+            Expression::Integer(n) if n.num().is_one()
                 => Ok(int!(0)),
 
-            Expression::Integer(n) if n.num() <= 0
-                => Err(UndefinedError("negative or zero logarithm".to_string())),
+            // Principal branch: ln(-n) = ln(n) + i*pi.
+            Expression::Integer(n) if n.num().is_negative() && settings::complex_mode()
+                => sum!(ln!(int!(-n.num())), prod!(imag!(), var!("pi"))).simplify(),
+
+            Expression::Integer(n) if !n.num().is_positive()
+                => Err(MathError::Undefined("negative or zero logarithm".to_string())),
 
             u => Ok(ln!(u)),
         }
     }
+
+    // `exp(ln(x)) = x` wherever the logarithm is defined, which extends to
+    // `exp(c*ln(x)) = x^c` and to terms of a sum, as in `exp(x + ln(2)) = 2*exp(x)`.
+    fn simplify_exp(u: Expression) -> Result<Expression, MathError> {
+        let terms = match &u {
+            Expression::Integer(n) if n.num().is_zero() => return Ok(int!(1)),
+            Expression::Integer(n) if n.num().is_one() => return Ok(var!("e")),
+            Expression::Sum(s) => s.values(),
+            u => std::slice::from_ref(u),
+        };
+
+        let mut factors = Vec::new();
+        let mut rest = Vec::new();
+        for v in terms {
+            match v {
+                Expression::Function(Function::Ln(x)) => factors.push(*x.clone()),
+                Expression::Product(p) => match p.values() {
+                    [c @ (Expression::Integer(_) | Expression::Rational(_)), Expression::Function(Function::Ln(x))]
+                        => factors.push(pow!(*x.clone(), c.clone())),
+                    _ => rest.push(v.clone()),
+                },
+                v => rest.push(v.clone()),
+            }
+        }
+
+        if factors.is_empty() {
+            return Function::euler(u)
+        }
+        factors.push(func!("exp"; Sum::new(rest).into()));
+        Product::new(factors).simplify()
+    }
+
+    // `exp(i*q*pi) = cos(q*pi) + i*sin(q*pi)` for quarter turns, where it is
+    // one of 1, i, -1 and -i.
+    fn euler(u: Expression) -> Result<Expression, MathError> {
+        if let Some(z) = Complex::split(&u)? {
+            let (q, rest) = Function::split_pi(&z.im)?;
+            let half = BigRational::new(1.into(), 2.into());
+            if z.re == int!(0) && rest == int!(0) && (&q * BigRational::from_integer(2.into())).is_integer() {
+                if let (Some(sin), Some(cos)) = (Function::sin_pi(&q), Function::sin_pi(&(half - &q))) {
+                    return sum!(cos, prod!(imag!(), sin)).simplify()
+                }
+            }
+        }
+        Ok(func!("exp"; u))
+    }
+
+    // Whether u has no imaginary part, which is the case for every
+    // expression outside of complex mode. In complex mode a symbol is only
+    // known to be real when it is assumed to be.
+    pub fn is_real(u: &Expression) -> Result<bool, MathError> {
+        if !settings::complex_mode() {
+            return Ok(true)
+        }
+        Ok(Function::real_parts(u)?.is_some_and(|z| z.im == int!(0)))
+    }
+
+    // The real and imaginary parts of u. `Complex::split` takes every symbol
+    // to be real, so the parts are only found when that is known, as it is
+    // outside of complex mode or for symbols assumed to be real.
+    fn real_parts(u: &Expression) -> Result<Option<Complex>, MathError> {
+        let symbols_real = !settings::complex_mode() || Polynomial::variables(u)
+            .iter()
+            .all(|x| matches!(x, Expression::Variable(v) if v.assumptions().real));
+        match symbols_real {
+            true => Complex::split(u),
+            false => Ok(None),
+        }
+    }
+
+    fn simplify_re(u: Expression) -> Result<Expression, MathError> {
+        match Function::real_parts(&u)? {
+            Some(z) => Ok(z.re),
+            None => Ok(func!("re"; u)),
+        }
+    }
+
+    fn simplify_im(u: Expression) -> Result<Expression, MathError> {
+        match Function::real_parts(&u)? {
+            Some(z) => Ok(z.im),
+            None => Ok(func!("im"; u)),
+        }
+    }
+
+    fn simplify_conj(u: Expression) -> Result<Expression, MathError> {
+        match Function::real_parts(&u)? {
+            Some(z) => z.conj().into_expression(),
+            None => Ok(func!("conj"; u)),
+        }
+    }
+
+    fn simplify_arg(u: Expression) -> Result<Expression, MathError> {
+        let Some(z) = Function::real_parts(&u)? else { return Ok(func!("arg"; u)) };
+        let (Some(re), Some(im)) = (z.re.sign(), z.im.sign()) else { return Ok(func!("arg"; u)) };
+
+        let pi = var!("pi");
+        let diagonal = sum!(z.re.clone(), neg!(z.im.clone())).simplify()? == int!(0);
+        let antidiagonal = sum!(z.re.clone(), z.im.clone()).simplify()? == int!(0);
+
+        match (re, im) {
+            (Ordering::Equal, Ordering::Equal)
+                => Err(MathError::Undefined("The argument of zero is undefined".to_string())),
+            (Ordering::Greater, Ordering::Equal) => Ok(int!(0)),
+            (Ordering::Less, Ordering::Equal) => Ok(pi),
+            (Ordering::Equal, Ordering::Greater) => prod!(frac!(1, 2), pi).simplify(),
+            (Ordering::Equal, Ordering::Less) => prod!(frac!(-1, 2), pi).simplify(),
+            (Ordering::Greater, Ordering::Greater) if diagonal => prod!(frac!(1, 4), pi).simplify(),
+            (Ordering::Less, Ordering::Less) if diagonal => prod!(frac!(-3, 4), pi).simplify(),
+            (Ordering::Less, Ordering::Greater) if antidiagonal => prod!(frac!(3, 4), pi).simplify(),
+            (Ordering::Greater, Ordering::Less) if antidiagonal => prod!(frac!(-1, 4), pi).simplify(),
+            _ => Ok(func!("arg"; u)),
+        }
+    }
+
+    // Trigonometric functions are reduced by their parity, by their period
+    // and by shifts of a quarter turn, and take exact values at the multiples
+    // of pi/12, pi/8 and pi/10, where the sine is known in square roots.
+    fn simplify_trig(name: &str, u: Expression) -> Result<Expression, MathError> {
+        if let Some(v) = Function::cancel_inverse(name, &u) {
+            return Ok(v)
+        }
+        if Function::is_negated(&u) {
+            let v = neg!(u).expand()?;
+            return match name {
+                "cos" | "sec" => func!(name; v).simplify(),
+                _ => neg!(func!(name; v)).simplify(),
+            }
+        }
+
+        let (q, rest) = Function::split_pi(&u)?;
+        if rest == int!(0) {
+            let half = BigRational::new(1.into(), 2.into());
+            if let (Some(sin), Some(cos)) = (Function::sin_pi(&q), Function::sin_pi(&(half - &q))) {
+                let value = match name {
+                    "sin" => sin,
+                    "cos" => cos,
+                    "tan" => div!(sin, cos),
+                    "sec" => inv!(cos),
+                    "csc" => inv!(sin),
+                    _ => div!(cos, sin),
+                };
+                return value.simplify().map_err(|_| MathError::Undefined(format!("{} is undefined at {}", name, u)))
+            }
+        }
+
+        if q.is_zero() {
+            return Ok(func!(name; u))
+        }
+
+        // A shift by k quarter turns swaps the function for its cofunction
+        // whenever k is odd, and changes its sign as in sin(x + pi) = -sin(x).
+        let quarters = &q * BigRational::from_integer(2.into());
+        if quarters.is_integer() {
+            let k = quarters.to_integer().mod_floor(&4.into()).to_u8().unwrap_or_default();
+            let (f, negate) = match (name, k) {
+                ("sin", 0) | ("cos", 3) => ("sin", false),
+                ("sin", 2) | ("cos", 1) => ("sin", true),
+                ("cos", 0) | ("sin", 1) => ("cos", false),
+                ("cos", 2) | ("sin", 3) => ("cos", true),
+                ("sec", 0) | ("csc", 1) => ("sec", false),
+                ("sec", 2) | ("csc", 3) => ("sec", true),
+                ("csc", 0) | ("sec", 3) => ("csc", false),
+                ("csc", 2) | ("sec", 1) => ("csc", true),
+                ("tan", 0 | 2) => ("tan", false),
+                ("tan", _) => ("cot", true),
+                ("cot", 0 | 2) => ("cot", false),
+                _ => ("tan", true),
+            };
+            return match negate {
+                true => neg!(func!(f; rest)).simplify(),
+                false => func!(f; rest).simplify(),
+            }
+        }
+
+        // Otherwise the multiple of pi is brought into (-p/2, p/2] for the period p.
+        let period = BigRational::from_integer(if matches!(name, "tan" | "cot") { 1 } else { 2 }.into());
+        let half_period = &period / BigRational::from_integer(2.into());
+        let reduced = &q - (&(&q + &half_period) / &period).ceil() * &period + &period;
+        if reduced == q {
+            return Ok(func!(name; u))
+        }
+        func!(name; sum!(rest, prod!(Polynomial::number(&reduced), var!("pi")))).simplify()
+    }
+
+    // Hyperbolic functions share the parity of their circular counterparts.
+    fn simplify_hyperbolic(name: &str, u: Expression) -> Result<Expression, MathError> {
+        if let Some(v) = Function::cancel_inverse(name, &u) {
+            return Ok(v)
+        }
+        if Function::is_negated(&u) {
+            let v = neg!(u).expand()?;
+            return match name {
+                "cosh" => func!(name; v).simplify(),
+                _ => neg!(func!(name; v)).simplify(),
+            }
+        }
+
+        match (name, u) {
+            ("cosh", u) if u == int!(0) => Ok(int!(1)),
+            (_, u) if u == int!(0) => Ok(int!(0)),
+            (_, u) => Ok(func!(name; u)),
+        }
+    }
+
+    // Inverse functions are odd apart from `acos(-x) = pi - acos(x)` and
+    // `acosh`, undo their forward function on its principal range, and take
+    // the known angles that are multiples of pi/12.
+    fn simplify_inverse(name: &str, u: Expression) -> Result<Expression, MathError> {
+        if name != "acosh" && Function::is_negated(&u) {
+            let v = neg!(u).expand()?;
+            return match name {
+                "acos" => sum!(var!("pi"), neg!(func!(name; v))).simplify(),
+                _ => neg!(func!(name; v)).simplify(),
+            }
+        }
+
+        let forward = &name[1..];
+        if let Expression::Function(g) = &u {
+            if g.name() == forward && Function::in_principal_range(name, g.arg()) {
+                return Ok(g.arg().clone())
+            }
+        }
+
+        match name {
+            "asinh" | "atanh" if u == int!(0) => return Ok(int!(0)),
+            "acosh" if u == int!(1) => return Ok(int!(0)),
+            "asin" | "acos" | "atan" if Polynomial::variables(&u).is_empty() => (),
+            _ => return Ok(func!(name; u)),
+        }
+
+        // The angle is recognised numerically, and then checked exactly
+        // whenever the argument is a rational number.
+        let Ok(angle) = Function::new(name, u.clone()).evaluate(MATCH_PRECISION) else {
+            return Ok(func!(name; u))
+        };
+        let twelfths = angle
+            .mul(&Float::from_int(12), MATCH_PRECISION)
+            .div(&Float::pi(MATCH_PRECISION), MATCH_PRECISION)?;
+        let Some(k) = twelfths.near_integer(MATCH_PRECISION - 28) else {
+            return Ok(func!(name; u))
+        };
+
+        let angle = prod!(Expression::Rational(Rational::new(k, 12)), var!("pi")).simplify()?;
+        if matches!(u, Expression::Integer(_) | Expression::Rational(_)) && func!(forward; angle.clone()).simplify()? != u {
+            return Ok(func!(name; u))
+        }
+        Ok(angle)
+    }
+
+    // f(g(v)) = v whenever g is the inverse of f.
+    fn cancel_inverse(name: &str, u: &Expression) -> Option<Expression> {
+        match u {
+            Expression::Function(g) if g.name().strip_prefix('a') == Some(name) => Some(g.arg().clone()),
+            _ => None,
+        }
+    }
+
+    // Whether v lies where the inverse function undoes its forward function.
+    // Every variable is real outside of complex mode, which covers `asinh`
+    // and `atanh`, while the circular ones need a value to compare with.
+    fn in_principal_range(name: &str, v: &Expression) -> bool {
+        if matches!(name, "asinh" | "atanh") {
+            return !settings::complex_mode()
+        }
+
+        let Ok(x) = v.evaluate(MATCH_PRECISION) else { return false };
+        let pi = Float::pi(MATCH_PRECISION);
+        let half_pi = pi.div(&Float::from_int(2), MATCH_PRECISION).unwrap_or_else(|_| pi.clone());
+        let zero = Float::from_int(0);
+        match name {
+            "asin" => -half_pi.clone() <= x && x <= half_pi,
+            "acos" => zero <= x && x <= pi,
+            "atan" => -half_pi.clone() < x && x < half_pi,
+            _ => zero <= x,
+        }
+    }
+
+    // sin(q*pi) for those q whose denominator divides 12, 8 or 10.
+    fn sin_pi(q: &BigRational) -> Option<Expression> {
+        let one = BigRational::one();
+        let two = BigRational::from_integer(2.into());
+        let q = q - (q / &two).floor() * &two;
+        if q >= one {
+            return Function::sin_pi(&(q - one)).map(|v| neg!(v))
+        }
+        if q > BigRational::new(1.into(), 2.into()) {
+            return Function::sin_pi(&(one - q))
+        }
+
+        let k = &q * BigRational::from_integer(120.into());
+        if !k.is_integer() {
+            return None
+        }
+        let root = |a: i64, b: i64, c: i64| sqrt!(sum!(int!(a), prod!(int!(b), sqrt!(int!(c)))));
+        match k.to_integer().to_u8()? {
+            0 => Some(int!(0)),
+            10 => Some(prod!(frac!(1, 4), sum!(sqrt!(int!(6)), neg!(sqrt!(int!(2)))))),
+            12 => Some(prod!(frac!(1, 4), sum!(sqrt!(int!(5)), int!(-1)))),
+            15 => Some(prod!(frac!(1, 2), root(2, -1, 2))),
+            20 => Some(frac!(1, 2)),
+            24 => Some(prod!(frac!(1, 4), root(10, -2, 5))),
+            30 => Some(prod!(frac!(1, 2), sqrt!(int!(2)))),
+            36 => Some(prod!(frac!(1, 4), sum!(sqrt!(int!(5)), int!(1)))),
+            40 => Some(prod!(frac!(1, 2), sqrt!(int!(3)))),
+            45 => Some(prod!(frac!(1, 2), root(2, 1, 2))),
+            48 => Some(prod!(frac!(1, 4), root(10, 2, 5))),
+            50 => Some(prod!(frac!(1, 4), sum!(sqrt!(int!(6)), sqrt!(int!(2))))),
+            60 => Some(int!(1)),
+            _ => None,
+        }
+    }
+
+    // Splits a simplified argument into q*pi plus the remaining terms.
+    fn split_pi(u: &Expression) -> Result<(BigRational, Expression), MathError> {
+        let is_pi = |v: &Expression| matches!(v, Expression::Variable(x) if x.as_str() == "pi");
+        let terms = match u {
+            Expression::Sum(s) => s.values(),
+            u => std::slice::from_ref(u),
+        };
+
+        let mut q = BigRational::zero();
+        let mut rest = Vec::new();
+        for v in terms {
+            match v {
+                v if is_pi(v) => q += BigRational::one(),
+                Expression::Product(p) => match p.values() {
+                    [c, x] if is_pi(x) && Polynomial::rational(c).is_some()
+                        => q += Polynomial::rational(c).unwrap_or_default(),
+                    _ => rest.push(v.clone()),
+                },
+                v => rest.push(v.clone()),
+            }
+        }
+
+        Ok((q, Sum::new(rest).simplify()?))
+    }
+
+    // Whether a simplified expression is written with a leading minus sign,
+    // which for a sum means every one of its terms.
+    fn is_negated(u: &Expression) -> bool {
+        match u {
+            Expression::Integer(_) | Expression::Rational(_) => u.sign() == Some(Ordering::Less),
+            Expression::Product(p) => p.values().first().is_some_and(|c| {
+                matches!(c, Expression::Integer(_) | Expression::Rational(_)) && c.sign() == Some(Ordering::Less)
+            }),
+            Expression::Sum(s) => s.values().iter().all(Function::is_negated),
+            _ => false,
+        }
+    }
+
+    // The largest or smallest of several values. Nested calls are flattened
+    // and the numbers among the values compared, keeping only the extreme one.
+    fn simplify_extremum(f: Function) -> Result<Expression, MathError> {
+        let wanted = match f {
+            Function::Max(_) => Ordering::Greater,
+            _ => Ordering::Less,
+        };
+
+        let mut values = Vec::new();
+        let mut number: Option<Expression> = None;
+        let mut pending: Vec<_> = f.args().into_iter().rev().cloned().collect();
+        while let Some(u) = pending.pop() {
+            match u.simplify()? {
+                Expression::Function(g) if g.name() == f.name() => pending.extend(g.args().into_iter().rev().cloned()),
+                u if u.sign().is_some() => {
+                    number = match number {
+                        Some(n) if sum!(u.clone(), neg!(n.clone())).simplify()?.sign() != Some(wanted) => Some(n),
+                        _ => Some(u),
+                    }
+                }
+                u if !values.contains(&u) => values.push(u),
+                _ => (),
+            }
+        }
+
+        values.extend(number);
+        values.sort();
+        match values.len() {
+            1 => Ok(values.remove(0)),
+            _ => Ok(Expression::Function(f.with_args(values))),
+        }
+    }
+
+    // The greatest common divisor or least common multiple of integers,
+    // which is left as it is when any value is not an integer.
+    fn simplify_gcd(f: Function) -> Result<Expression, MathError> {
+        let args = f.args().into_iter().map(|u| u.clone().simplify()).collect::<Result<Vec<_>, _>>()?;
+        let numbers: Option<Vec<&BigInt>> = args.iter()
+            .map(|u| match u {
+                Expression::Integer(n) => Some(n.num()),
+                _ => None,
+            })
+            .collect();
+        let Some(numbers) = numbers else {
+            return Ok(Expression::Function(f.with_args(args)))
+        };
+
+        Ok(int!(match f {
+            Function::Gcd(_) => numbers.into_iter().fold(BigInt::zero(), |a, n| a.gcd(n)),
+            _ => numbers.into_iter().fold(BigInt::one(), |a, n| a.lcm(n)),
+        }))
+    }
+
+    // Constant factors are dropped from an order term, so that its argument
+    // is a power of `x`, of `x - a`, or of `1/x` near infinity.
+    fn simplify_order(u: Expression) -> Result<Expression, MathError> {
+        let vars = Polynomial::variables(&u);
+        let factors: Vec<_> = u.term()
+            .iter()
+            .filter(|v| Polynomial::variables(v).iter().any(|x| vars.contains(x)))
+            .cloned()
+            .collect();
+
+        Ok(func!("O"; Product::new(factors).simplify()?))
+    }
+
+    // Whether the order term `O(g)` absorbs u, which is the case for
+    // constant multiples of `b^k` where `g = b^n` and k is at least n. A
+    // negative n means `x` tends to infinity, where k has to be at most n.
+    pub fn order_absorbs(g: &Expression, u: &Expression) -> bool {
+        let u = match u {
+            Expression::Function(Function::Order(h)) => h.as_ref(),
+            u => u,
+        };
+        let (b, n) = match g {
+            Expression::Power(p) => (p.base.as_ref(), p.exp.as_ref().clone()),
+            Expression::Variable(_) | Expression::Sum(_) => (g, int!(1)),
+            _ => return false,
+        };
+        let vars = Polynomial::variables(b);
+
+        let mut k = int!(0);
+        let mut found = false;
+        for v in u.term().iter().filter(|v| Polynomial::variables(v).iter().any(|x| vars.contains(x))) {
+            match (found, v) {
+                (false, Expression::Power(p)) if p.base.as_ref() == b => k = p.exp.as_ref().clone(),
+                (false, v) if v == b => k = int!(1),
+                _ => return false,
+            }
+            found = true;
+        }
+
+        let Ok(difference) = sum!(k, neg!(n.clone())).simplify() else { return false };
+        match (n.sign(), difference.sign()) {
+            (Some(Ordering::Less), Some(Ordering::Less | Ordering::Equal)) => matches!(b, Expression::Variable(_)),
+            (Some(Ordering::Greater), Some(Ordering::Greater | Ordering::Equal)) => true,
+            _ => false,
+        }
+    }
+
+    // Evaluates an angle with extra bits for its integer part, which are
+    // lost when it is reduced by multiples of pi.
+    fn evaluate_angle(u: &Expression, prec: u64) -> Result<Float, MathError> {
+        let x = u.evaluate(prec)?;
+        match x.top() {
+            top if top > 0 => u.evaluate(prec + top as u64),
+            _ => Ok(x),
+        }
+    }
+
+    fn evaluate_parts(u: &Expression, prec: u64) -> Result<(Float, Float), MathError> {
+        match Complex::split(u)? {
+            Some(z) => Ok((z.re.evaluate(prec)?, z.im.evaluate(prec)?)),
+            None => Err(MathError::Undefined(format!("Cannot separate `{}` into real and imaginary parts", u))),
+        }
+    }
 }
 
 impl cmp::Ord for Function {
@@ -130,9 +1154,223 @@ impl fmt::Display for Function {
         match self {
             Function::Sqrt(u) => write!(f, "sqrt({})", u),
             Function::Cbrt(u) => write!(f, "cbrt({})", u),
-            Function::Log(u) => write!(f, "log({})", u),
+            Function::Abs(u) => write!(f, "|{}|", u),
+            Function::Log(b, u) if b.as_ref() == &int!(10) => write!(f, "log({})", u),
+            Function::Log(b, u) => write!(f, "log({}, {})", b, u),
             Function::Ln(u) => write!(f, "ln({})", u),
-            Function::Other(name, u) => write!(f, "{}({})", name, u)
+            Function::Exp(u) => write!(f, "exp({})", u),
+            Function::Re(u) => write!(f, "re({})", u),
+            Function::Im(u) => write!(f, "im({})", u),
+            Function::Conj(u) => write!(f, "conj({})", u),
+            Function::Arg(u) => write!(f, "arg({})", u),
+            Function::Sin(u) => write!(f, "sin({})", u),
+            Function::Cos(u) => write!(f, "cos({})", u),
+            Function::Tan(u) => write!(f, "tan({})", u),
+            Function::Sec(u) => write!(f, "sec({})", u),
+            Function::Csc(u) => write!(f, "csc({})", u),
+            Function::Cot(u) => write!(f, "cot({})", u),
+            Function::Asin(u) => write!(f, "asin({})", u),
+            Function::Acos(u) => write!(f, "acos({})", u),
+            Function::Atan(u) => write!(f, "atan({})", u),
+            Function::Sinh(u) => write!(f, "sinh({})", u),
+            Function::Cosh(u) => write!(f, "cosh({})", u),
+            Function::Tanh(u) => write!(f, "tanh({})", u),
+            Function::Asinh(u) => write!(f, "asinh({})", u),
+            Function::Acosh(u) => write!(f, "acosh({})", u),
+            Function::Atanh(u) => write!(f, "atanh({})", u),
+            Function::Order(u) => write!(f, "O({})", u),
+            Function::Root(..)
+            | Function::Max(_)
+            | Function::Min(_)
+            | Function::Gcd(_)
+            | Function::Lcm(_)
+            | Function::Other(..) => write!(f, "{}({})", self.name(), self.args()
+                .iter()
+                .map(|u| format!("{}", u))
+                .collect::<Vec<_>>()
+                .join(", ")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, try_parse};
+    use crate::settings::Property;
+
+    #[test]
+    fn inverse_rules_hold_for_real_arguments() {
+        assert_eq!(parse("sqrt(x^2)"), parse("|x|"));
+        assert_eq!(parse("ln(exp(x))"), parse("x"));
+    }
+
+    #[test]
+    fn complex_symbols_must_be_assumed_real() {
+        settings::set_complex_mode(true);
+        assert_eq!(parse("sqrt(x^2)"), sqrt!(pow!(var!("x"), int!(2))));
+        assert_eq!(parse("ln(exp(x))"), ln!(func!("exp"; var!("x"))));
+        assert_eq!(parse("ln(exp(2 pi i))"), int!(0));
+
+        settings::assume("x", Property::Real);
+        assert_eq!(parse("sqrt(x^2)"), parse("|x|"));
+        assert_eq!(parse("ln(exp(x))"), parse("x"));
+    }
+
+    #[test]
+    fn trigonometric_values_at_multiples_of_pi() {
+        assert_eq!(parse("sin(pi/6)"), parse("1/2"));
+        assert_eq!(parse("cos(pi/4)"), parse("sqrt(2)/2"));
+        assert_eq!(parse("tan(pi/3)"), parse("sqrt(3)"));
+        assert_eq!(parse("sin(pi/12)"), parse("(sqrt(6) - sqrt(2))/4"));
+        assert_eq!(parse("cos(pi)"), int!(-1));
+        assert!(try_parse("tan(pi/2)").is_err());
+    }
+
+    #[test]
+    fn trigonometric_values_at_fifths_eighths_and_tenths() {
+        assert_eq!(parse("cos(pi/5)"), parse("(1 + sqrt(5))/4"));
+        assert_eq!(parse("sin(pi/10)"), parse("(sqrt(5) - 1)/4"));
+        assert_eq!(parse("sin(pi/8)"), parse("sqrt(2 - sqrt(2))/2"));
+        assert_eq!(parse("cos(pi/8)"), parse("sqrt(2 + sqrt(2))/2"));
+        assert_eq!(parse("sin(2pi/5)"), parse("sqrt(10 + 2sqrt(5))/4"));
+        assert_eq!(parse("sin(pi/7)"), func!("sin"; parse("pi/7")));
+
+        // Every known value agrees with the numerical sine.
+        for k in 0..240 {
+            let q = BigRational::new(k.into(), 120.into());
+            let Some(exact) = Function::sin_pi(&q) else { continue };
+            let angle = prod!(Polynomial::number(&q), var!("pi"));
+            let error = exact.evaluate(100).unwrap().sub(&func!("sin"; angle).evaluate(100).unwrap(), 100);
+            assert_eq!(error.near_integer(80), Some(0.into()), "sin({}*pi)", q);
+        }
+    }
+
+    #[test]
+    fn trigonometric_parity_and_period() {
+        assert_eq!(parse("sin(-x)"), parse("-sin(x)"));
+        assert_eq!(parse("cos(-x)"), parse("cos(x)"));
+        assert_eq!(parse("sin(x + 2pi)"), parse("sin(x)"));
+        assert_eq!(parse("cos(x + pi)"), parse("-cos(x)"));
+        assert_eq!(parse("sin(x + pi/2)"), parse("cos(x)"));
+    }
+
+    #[test]
+    fn trigonometric_functions_evaluate() {
+        let evalf = |text: &str| parse(text).evalf(20).unwrap().to_decimal(20);
+        assert_eq!(evalf("sin(1)"), "0.84147098480789650665");
+        assert_eq!(evalf("cos(100)"), "0.86231887228768393410");
+        assert_eq!(evalf("tan(1/2)"), "0.54630248984379051326");
+    }
+
+    #[test]
+    fn inverse_trigonometric_values() {
+        assert_eq!(parse("asin(1/2)"), parse("pi/6"));
+        assert_eq!(parse("acos(-1/2)"), parse("2pi/3"));
+        assert_eq!(parse("atan(sqrt(3))"), parse("pi/3"));
+        assert_eq!(parse("acos(-x)"), parse("pi - acos(x)"));
+        assert_eq!(parse("atan(-x)"), parse("-atan(x)"));
+        assert_eq!(parse("asin(2)"), func!("asin"; int!(2)));
+    }
+
+    #[test]
+    fn inverses_cancel_on_their_principal_range() {
+        assert_eq!(parse("sin(asin(x))"), parse("x"));
+        assert_eq!(parse("asin(sin(1/2))"), parse("1/2"));
+        assert_eq!(parse("acos(cos(1))"), int!(1));
+        assert_eq!(parse("acos(cos(4))"), func!("acos"; func!("cos"; int!(4))));
+        assert_eq!(parse("asinh(sinh(x))"), parse("x"));
+        assert_eq!(parse("tanh(atanh(x))"), parse("x"));
+    }
+
+    #[test]
+    fn hyperbolic_functions() {
+        assert_eq!(parse("sinh(0)"), int!(0));
+        assert_eq!(parse("cosh(-x)"), parse("cosh(x)"));
+        assert_eq!(parse("tanh(-x)"), parse("-tanh(x)"));
+        assert_eq!(parse("acosh(1)"), int!(0));
+        assert_eq!(parse("sinh(x)").derivative(&var!("x")).unwrap(), parse("cosh(x)"));
+        assert_eq!(parse("atan(x)").derivative(&var!("x")).unwrap(), parse("1/(1 + x^2)"));
+    }
+
+    #[test]
+    fn inverse_and_hyperbolic_functions_evaluate() {
+        let evalf = |text: &str| parse(text).evalf(20).unwrap().to_decimal(20);
+        assert_eq!(evalf("acos(1/3)"), "1.2309594173407746821");
+        assert_eq!(evalf("acosh(2)"), "1.3169578969248167086");
+        assert_eq!(evalf("sinh(1)"), "1.1752011936438014569");
+        assert_eq!(evalf("atanh(1/2)"), "0.54930614433405484570");
+    }
+
+    #[test]
+    fn exponentials_and_natural_logarithms() {
+        assert_eq!(parse("e^x"), parse("exp(x)"));
+        assert_eq!(parse("exp(ln(x))"), parse("x"));
+        assert_eq!(parse("exp(a) exp(b)"), parse("exp(a + b)"));
+        assert_eq!(parse("exp(0)"), int!(1));
+        assert_eq!(parse("ln(e)"), int!(1));
+        assert_eq!(parse("exp(2x)").derivative(&var!("x")).unwrap(), parse("2 exp(2x)"));
+        assert_eq!(parse("exp(1/2)").evalf(20).unwrap().to_decimal(20), "1.6487212707001281468");
+    }
+
+    #[test]
+    fn logarithms_to_any_base() {
+        assert_eq!(parse("log(2, 32)"), int!(5));
+        assert_eq!(parse("log(4, 8)"), parse("3/2"));
+        assert_eq!(parse("log(1000)"), int!(3));
+        assert_eq!(parse("log(2, 1/8)"), int!(-3));
+        assert_eq!(parse("log(1/2, 4)"), int!(-2));
+        assert_eq!(parse("log(8, sqrt(2))"), parse("1/6"));
+        assert_eq!(parse("log(b, b^x)"), parse("x"));
+        assert_eq!(parse("log(2, 6)"), Expression::Function(Function::Log(Box::new(int!(2)), Box::new(int!(6)))));
+        assert_eq!(parse("log(2, 10)").evalf(20).unwrap().to_decimal(20), "3.3219280948873623479");
+    }
+
+    #[test]
+    fn logarithms_need_a_valid_base_and_argument() {
+        assert!(try_parse("log(1, 5)").is_err());
+        assert!(try_parse("log(0, 5)").is_err());
+        assert!(try_parse("log(2, 0)").is_err());
+    }
+
+    #[test]
+    fn functions_of_several_arguments() {
+        assert_eq!(parse("max(1, 2, x)"), parse("max(2, x)"));
+        assert_eq!(parse("max(x, max(y, 2))"), parse("max(2, x, y)"));
+        assert_eq!(parse("min(3, 1/2)"), parse("1/2"));
+        assert_eq!(parse("min(x, x)"), parse("x"));
+        assert_eq!(parse("gcd(12, 18)"), int!(6));
+        assert_eq!(parse("lcm(2, 3, 4)"), int!(12));
+        assert_eq!(parse("root(32, 5)"), int!(2));
+        assert_eq!(parse("root(-8, 3)"), int!(-2));
+        assert_eq!(parse("root(x, 5)"), parse("x^(1/5)"));
+    }
+
+    #[test]
+    fn unknown_functions_keep_their_arguments() {
+        assert_eq!(parse("f(x, y)").to_string(), "f(x, y)");
+        assert_eq!(parse("g(x) + f(x)"), parse("f(x) + g(x)"));
+        assert_eq!(parse("f(x, y) - f(x, y)"), int!(0));
+        assert_eq!(parse("f(x, y) f(x, y)"), parse("f(x, y)^2"));
+    }
+
+    #[test]
+    fn absolute_values() {
+        assert_eq!(parse("|-3|"), int!(3));
+        assert_eq!(parse("abs(-x)"), parse("|x|"));
+        assert_eq!(parse("|x y|"), parse("|x| |y|"));
+        assert_eq!(parse("|x|^2"), parse("x^2"));
+        assert_eq!(parse("sqrt(x^2)"), parse("|x|"));
+        assert_eq!(parse("||x||"), parse("|x|"));
+        assert_eq!(parse("|x|").derivative(&var!("x")).unwrap(), parse("x/|x|"));
+    }
+
+    #[test]
+    fn huge_angles_are_reduced() {
+        let evalf = |text: &str| parse(text).evalf(30).map(|x| x.to_decimal(30));
+        assert_eq!(evalf("sin(10^30)").unwrap(), "-0.0901169019121380580303864289530");
+        assert_eq!(evalf("cos(2^1000)").unwrap(), "0.987246077598913484239901796329");
+        assert_eq!(evalf("sin(10^30/7)").unwrap(), "0.977715415184553074904938673847");
+        assert!(matches!(evalf("sin(10^2000)"), Err(MathError::Overflow(_))));
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::traits::{Evaluate, Expand, Simplify};
+use crate::types::{Integer, Product, Sum};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Relation {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    NotEqual,
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Relation::Less => write!(f, "<"),
+            Relation::LessEqual => write!(f, "<="),
+            Relation::Greater => write!(f, ">"),
+            Relation::GreaterEqual => write!(f, ">="),
+            Relation::NotEqual => write!(f, "!="),
+        }
+    }
+}
+
+// A statement such as `lhs < rhs`, which like an equation is only
+// simplified side by side.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Inequality {
+    pub lhs: Box<Expression>,
+    pub rhs: Box<Expression>,
+    pub relation: Relation,
+}
+
+impl Simplify for Inequality {
+    fn simplify(self) -> Result<Expression, MathError> {
+        Ok(Inequality::new(self.lhs.simplify()?, self.relation, self.rhs.simplify()?).into())
+    }
+}
+
+impl Evaluate for Inequality {
+    fn evaluate(&self, _prec: u64) -> Result<Float, MathError> {
+        Err(MathError::Undefined("Cannot evaluate an inequality".to_string()))
+    }
+}
+
+impl Expand for Inequality {
+    fn expand(self) -> Result<Expression, MathError> {
+        Ok(Inequality::new(self.lhs.expand()?, self.relation, self.rhs.expand()?).into())
+    }
+}
+
+impl Inequality {
+    pub fn new(lhs: Expression, relation: Relation, rhs: Expression) -> Inequality {
+        Inequality {
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            relation,
+        }
+    }
+
+    // The equivalent expression `lhs - rhs`, to be compared with zero.
+    pub fn zero_form(&self) -> Result<Expression, MathError> {
+        sum!(*self.lhs.clone(), neg!(*self.rhs.clone())).simplify()
+    }
+}
@@ -1,23 +1,72 @@
 use std::cmp;
 
-use crate::expression::{Expression, UndefinedError};
-use crate::traits::Simplify;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer as _;
+use num_traits::{One, Signed, ToPrimitive, Zero};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Integer(i32);
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::traits::{Evaluate, Simplify};
+
+// Largest size, in bits, an exact integer may grow to before an
+// operation reports an overflow instead of trying to compute it.
+const MAX_BITS: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Integer(BigInt);
 
 impl Simplify for Integer {
-    fn simplify(self) -> Result<Expression, UndefinedError> {
+    fn simplify(self) -> Result<Expression, MathError> {
         Ok(self.into())
     }
 }
 
+impl Evaluate for Integer {
+    fn evaluate(&self, _prec: u64) -> Result<Float, MathError> {
+        Ok(Float::from_int(self.0.clone()))
+    }
+}
+
 impl Integer {
-    pub fn new(n: i32) -> Integer {
-        Integer(n)
+    pub fn new(n: impl Into<BigInt>) -> Integer {
+        Integer(n.into())
+    }
+
+    pub fn num(&self) -> &BigInt {
+        &self.0
+    }
+
+    pub fn checked_pow(base: &BigInt, exp: &BigUint) -> Result<BigInt, MathError> {
+        if base.is_zero() || base.is_one() {
+            return Ok(base.clone())
+        }
+
+        if base.magnitude().is_one() {
+            return Ok(if exp.is_even() { BigInt::one() } else { base.clone() })
+        }
+
+        match exp.to_u32() {
+            Some(e) if base.bits().saturating_mul(e as u64) <= MAX_BITS => Ok(base.pow(e)),
+            _ => Err(MathError::Overflow(format!("{}^{} is too large to compute exactly", base, exp)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, try_parse};
+
+    #[test]
+    fn large_powers_are_exact() {
+        assert_eq!(parse("2^100"), int!(BigInt::one() << 100));
+        assert_eq!(parse("(-1)^(10^30)"), int!(1));
     }
 
-    pub fn num(&self) -> i32 {
-        self.0
+    #[test]
+    fn oversized_results_report_an_overflow() {
+        assert!(Integer::checked_pow(&BigInt::from(2), &BigUint::from(MAX_BITS + 1)).is_err());
+        assert!(matches!(try_parse("2^(10^9)"), Err(MathError::Overflow(_))));
+        assert!(matches!(try_parse("3^3^3^3"), Err(MathError::Overflow(_))));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,54 @@
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+
+// A brace-delimited list such as `{2x + y = 3, x - y = 0}`, whose elements
+// are simplified one by one.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct List(Vec<Expression>);
+
+impl Simplify for List {
+    fn simplify(self) -> Result<Expression, MathError> {
+        Ok(List::new(self.0
+            .into_iter()
+            .map(Expression::simplify)
+            .collect::<Result<Vec<_>, _>>()?
+        ).into())
+    }
+}
+
+impl Evaluate for List {
+    fn evaluate(&self, _prec: u64) -> Result<Float, MathError> {
+        Err(MathError::Undefined("Cannot evaluate a list".to_string()))
+    }
+}
+
+impl Expand for List {
+    fn expand(self) -> Result<Expression, MathError> {
+        Ok(List::new(self.0
+            .into_iter()
+            .map(Expression::expand)
+            .collect::<Result<Vec<_>, _>>()?
+        ).into())
+    }
+}
+
+impl Differentiate for List {
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        Ok(List::new(self.0
+            .iter()
+            .map(|u| u.derivative(x))
+            .collect::<Result<Vec<_>, _>>()?
+        ).into())
+    }
+}
+
+impl List {
+    pub fn new(values: Vec<Expression>) -> List {
+        List(values)
+    }
+
+    pub fn values(&self) -> &[Expression] {
+        self.0.as_slice()
+    }
+}
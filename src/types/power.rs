@@ -3,9 +3,22 @@ use std::cmp;
 use std::ops::Deref;
 use std::rc::Rc;
 
-use crate::expression::{Expression, UndefinedError};
-use crate::traits::Simplify;
-use crate::types::{self, Integer, Rational, Product};
+use num_bigint::{BigInt, BigUint};
+use num_integer::{Integer as _, Roots};
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::limit;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+use crate::settings;
+use crate::types::{self, Integer, Rational, Product, Sum, Variable, Complex, Function};
+
+// Largest number of terms `expand` may produce when multiplying out a power of a sum.
+const MAX_EXPANSION_TERMS: u64 = 5_000;
+
+// Largest candidate factor tried when pulling perfect powers out of a radical.
+const RADICAL_TRIAL_LIMIT: u32 = 10_000;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Power {
@@ -14,7 +27,7 @@ pub struct Power {
 }
 
 impl Simplify for Power {
-    fn simplify(self) -> Result<Expression, UndefinedError> {
+    fn simplify(self) -> Result<Expression, MathError> {
         match (self.base.simplify()?, self.exp.simplify()?) {
             (Expression::Integer(n), Expression::Rational(q))
                 => Power::with_radical(n, q),
@@ -22,13 +35,40 @@ impl Simplify for Power {
             (Expression::Integer(n), w) 
                 => Power::with_integer_base(n, w),
 
+            (Expression::Function(Function::Order(g)), Expression::Integer(n)) if n.num().is_positive()
+                => func!("O"; pow!(*g, Expression::Integer(n))).simplify(),
+
+            // `oo^w` is `oo` for a positive w and 0 for a negative one.
+            (Expression::Variable(v), w) if v.as_str() == limit::INFINITY && w.sign().is_some_and(|s| s.is_ne())
+                => match w.sign() {
+                    Some(cmp::Ordering::Greater) => Ok(Expression::Variable(v)),
+                    _ => Ok(int!(0)),
+                },
+
+            // Powers of e are the exponential function, which integer powers stay.
+            (Expression::Variable(v), w) if v.as_str() == "e"
+                => func!("exp"; w).simplify(),
+
+            (Expression::Function(Function::Exp(u)), Expression::Integer(n))
+                => func!("exp"; prod!(*u, Expression::Integer(n))).simplify(),
+
+            // `|x|^2 = x^2` for real x.
+            (Expression::Function(Function::Abs(u)), Expression::Integer(n)) if n.num().is_even() && Function::is_real(&u)?
+                => pow!(*u, Expression::Integer(n)).simplify(),
+
             (v, Expression::Integer(n)) 
                 => Power::with_integer_exp(v, n),
+
+            (Expression::Power(p), w)
+                => Power::with_power_base(*p.base, *p.exp, w),
+
+            (Expression::Product(p), w)
+                => Power::with_product_base(p, w),
             
             (Expression::Rational(r), w)
                 => Ok(div!(
-                    pow!(int!(r.num()), w.clone()).simplify()?,
-                    pow!(int!(r.den()), w).simplify()?
+                    pow!(int!(r.num().clone()), w.clone()).simplify()?,
+                    pow!(int!(r.den().clone()), w).simplify()?
                 )),
                 
             (v, w) 
@@ -37,6 +77,63 @@ impl Simplify for Power {
     }
 }
 
+impl Evaluate for Power {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        let base = self.base.evaluate(prec)?;
+        match self.exp.as_ref() {
+            Expression::Integer(n) => base.powi(n.num(), prec),
+
+            // Odd roots of negative numbers still have a real value.
+            Expression::Rational(q) if base.is_negative() && q.den().is_odd() => {
+                let root = base.abs().pow(&q.evaluate(prec)?, prec)?;
+                Ok(if q.num().is_odd() { -root } else { root })
+            }
+
+            u => base.pow(&u.evaluate(prec)?, prec)
+        }
+    }
+}
+
+impl Expand for Power {
+    fn expand(self) -> Result<Expression, MathError> {
+        let base = self.base.expand()?;
+        match *self.exp {
+            Expression::Integer(n) if n.num().is_negative() => {
+                let denominator = Power::expand_power(base, n.num().magnitude())?;
+                pow!(denominator, int!(-1)).simplify()
+            }
+
+            Expression::Integer(n)
+                => Power::expand_power(base, n.num().magnitude()),
+
+            exp => pow!(base, exp.expand()?).simplify(),
+        }
+    }
+}
+
+impl Differentiate for Power {
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        let (u, v) = (self.base.as_ref(), self.exp.as_ref());
+
+        // d/dx u^v = v*u^(v-1)*u' when v is constant, u^v*ln(u)*v' when u is
+        // constant, and u^v*(v'*ln(u) + v*u'/u) in general.
+        match (u.free_of(x), v.free_of(x)) {
+            (true, true) => Ok(int!(0)),
+            (false, true)
+                => prod!(v.clone(), pow!(u.clone(), sum!(v.clone(), int!(-1))), u.derivative(x)?).simplify(),
+            (true, false)
+                => prod!(pow!(u.clone(), v.clone()), ln!(u.clone()), v.derivative(x)?).simplify(),
+            (false, false) => prod!(
+                pow!(u.clone(), v.clone()),
+                sum!(
+                    prod!(v.derivative(x)?, ln!(u.clone())),
+                    prod!(v.clone(), u.derivative(x)?, inv!(u.clone())),
+                ),
+            ).simplify(),
+        }
+    }
+}
+
 impl cmp::Ord for Power {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         if self.base != other.base {
@@ -59,47 +156,154 @@ impl Power {
         Power { base: Box::new(base), exp: Box::new(exp) }
     }
 
-    fn with_integer_base(n: Integer, w: Expression) -> Result<Expression, UndefinedError> {
+    // Multiplies out u^n for an expanded u, using binomial coefficients
+    // on (f + r)^n, where f is the first term of the sum and r the rest.
+    fn expand_power(u: Expression, n: &BigUint) -> Result<Expression, MathError> {
+        let Expression::Sum(s) = u else {
+            return pow!(u, int!(BigInt::from(n.clone()))).simplify()
+        };
+
+        let terms = s.values().len() as u64;
+        let too_large = || MathError::Overflow(format!("expanding ({})^{} gives too many terms", Expression::Sum(s.clone()), n));
+
+        // The expansion has C(n + m - 1, m - 1) terms for a sum of m terms.
+        let n = n.to_u64().ok_or_else(too_large)?;
+        let mut count = 1u64;
+        for k in 1..terms {
+            count = count.saturating_mul(n + k) / k;
+            if count > MAX_EXPANSION_TERMS {
+                return Err(too_large())
+            }
+        }
+
+        let (f, r) = s.values().split_first().unwrap();
+        let r = Sum::new(r.to_vec()).simplify()?;
+
+        let mut result = Vec::new();
+        let mut coeff = BigInt::one();
+
+        for k in 0..=n {
+            let head = prod!(int!(coeff.clone()), pow!(f.clone(), int!(n - k))).simplify()?;
+            let tail = Power::expand_power(r.clone(), &BigUint::from(k))?;
+            result.push(Product::expand_product(head, tail)?);
+            coeff = coeff * (n - k) / (k + 1);
+        }
+
+        Sum::new(result).simplify()
+    }
+
+    fn with_integer_base(n: Integer, w: Expression) -> Result<Expression, MathError> {
         match w {
-            Expression::Integer(m) if m.num().is_positive() && n.num() == 0
+            Expression::Integer(m) if m.num().is_positive() && n.num().is_zero()
                 => Ok(int!(0)),
 
-            Expression::Rational(..) if n.num() == 0 => Ok(int!(0)),
+            Expression::Integer(m) if m.num().is_negative() && n.num().is_zero()
+                => Err(MathError::Undefined("Indeterminate form: k/0".to_string())),
+
+            Expression::Rational(..) if n.num().is_zero() => Ok(int!(0)),
 
-            _ if n.num() == 0 => Err(UndefinedError("Indeterminate form: 0^0".to_string())),
+            _ if n.num().is_zero() => Err(MathError::Undefined("Indeterminate form: 0^0".to_string())),
 
-            _ if n.num().abs() == 1 => Ok(n.into()),
+            _ if n.num().is_one() => Ok(n.into()),
+
+            // `(-1)^(2k) = 1` and `(-1)^(2k + 1) = -1` for an integer k.
+            w if n.num() == &BigInt::from(-1) && prod!(frac!(1, 2), w.clone()).expand()?.is_integer()
+                => Ok(int!(1)),
+
+            w if n.num() == &BigInt::from(-1) && prod!(frac!(1, 2), sum!(w.clone(), int!(-1))).expand()?.is_integer()
+                => Ok(int!(-1)),
 
             Expression::Integer(m) if m.num().is_negative()
-                => Ok(frac!(1, n.num().pow(m.num().unsigned_abs()))),
+                => frac!(1, Integer::checked_pow(n.num(), m.num().magnitude())?).simplify(),
 
             Expression::Integer(m)
-                => Ok(int!(n.num().pow(m.num().unsigned_abs()))),
+                => Ok(int!(Integer::checked_pow(n.num(), m.num().magnitude())?)),
 
             w => Ok(pow!(n.into(), w))
         }
     }
 
-    fn with_integer_exp(v: Expression, n: Integer) -> Result<Expression, UndefinedError> {
-        match (v, n) {
-            (Expression::Rational(q), n) if n.num().is_negative()
-                => Ok(frac!(
-                    q.den().pow(n.num() as u32), 
-                    q.num().pow(n.num() as u32)
-                )),
+    // `(b^a)^w = b^(a*w)` for a nonnegative b, and for a real b with `|b|`
+    // in its place when an even power hides its sign, as in `(x^2)^(1/2) = |x|`.
+    // In real mode odd roots keep the sign, as in `(x^2)^(1/3) = x^(2/3)`,
+    // and other powers are only defined where merging the exponents agrees.
+    fn with_power_base(b: Expression, a: Expression, w: Expression) -> Result<Expression, MathError> {
+        let real_mode = !settings::complex_mode();
+        let even = match &a {
+            Expression::Integer(n) => n.num().is_even(),
+            Expression::Rational(q) => real_mode && q.num().is_even(),
+            _ => false,
+        };
+        let odd_root = matches!(&w, Expression::Rational(q) if q.den().is_odd());
+        let exp = prod!(a.clone(), w.clone());
 
-            (Expression::Rational(q), n)
-                => Ok(frac!(
-                    q.num().pow(n.num() as u32),
-                    q.den().pow(n.num() as u32) 
-                )),
+        if b.is_nonnegative() || (real_mode && odd_root) {
+            pow!(b, exp).simplify()
+        }
+        else if even && Function::is_real(&b)? {
+            pow!(func!("abs"; b), exp).simplify()
+        }
+        else if real_mode && matches!(w, Expression::Rational(_)) {
+            pow!(b, exp).simplify()
+        }
+        else {
+            Ok(pow!(pow!(b, a), w))
+        }
+    }
+
+    // `(x*y)^w = x^w * y^w` for a nonnegative x, as in `(4x)^(1/2) = 2*x^(1/2)`.
+    // The remaining factors stay together under the power.
+    fn with_product_base(p: Product, w: Expression) -> Result<Expression, MathError> {
+        let mut factors = Vec::new();
+        let mut rest = Vec::new();
+        for v in p.values() {
+            match v {
+                v if v.is_nonnegative() => factors.push(pow!(v.clone(), w.clone())),
+                // A negative number c leaves -1 behind in its place.
+                Expression::Integer(_) | Expression::Rational(_) if v != &int!(-1) => {
+                    factors.push(pow!(neg!(v.clone()), w.clone()));
+                    rest.push(int!(-1));
+                }
+                v => rest.push(v.clone()),
+            }
+        }
 
-            (_, n) if n.num() == 0
+        if factors.is_empty() {
+            return Ok(pow!(Expression::Product(p), w))
+        }
+        if !rest.is_empty() {
+            factors.push(pow!(Product::new(rest).simplify()?, w));
+        }
+        Product::new(factors).simplify()
+    }
+
+    fn with_integer_exp(v: Expression, n: Integer) -> Result<Expression, MathError> {
+        match (v, n) {
+            (Expression::Rational(q), n) => {
+                let num = Integer::checked_pow(q.num(), n.num().magnitude())?;
+                let den = Integer::checked_pow(q.den(), n.num().magnitude())?;
+                match n.num().is_negative() {
+                    true => frac!(den, num).simplify(),
+                    false => frac!(num, den).simplify(),
+                }
+            },
+
+            (_, n) if n.num().is_zero()
                 => Ok(int!(1)),
 
-            (v, n) if n.num() == 1
+            (v, n) if n.num().is_one()
                 => Ok(v),
 
+            (v, n) if v.is_imaginary_unit() => match n.num().mod_floor(&BigInt::from(4)).to_u32() {
+                Some(1) => Ok(imag!()),
+                Some(2) => Ok(int!(-1)),
+                Some(3) => Ok(neg!(imag!())),
+                _ => Ok(int!(1)),
+            },
+
+            (v @ Expression::Sum(_), n) if Complex::gaussian(&v).is_some()
+                => Complex::gaussian(&v).unwrap().pow(n.num())?.into_expression(),
+
             (Expression::Power(p), n) => {
                 let u = prod!(*p.exp, n.into()).simplify()?;
                 match u {
@@ -111,8 +315,8 @@ impl Power {
             (Expression::Product(r), n)
                 => Product::new(r.values()
                     .iter()
-                    .map(|v| Power::with_integer_exp(v.clone(), n))
-                    .collect::<Result<Vec<_>, UndefinedError>>()?
+                    .map(|v| Power::with_integer_exp(v.clone(), n.clone()))
+                    .collect::<Result<Vec<_>, MathError>>()?
                 ).simplify(),
             
             (v, n)
@@ -120,21 +324,60 @@ impl Power {
         }
     }
 
-    fn with_radical(n: Integer, q: Rational) -> Result<Expression, UndefinedError> {
-        if q.den() % 2 == 0 && n.num().is_negative() {
-            return Err(UndefinedError("Negative value under even root".to_string()))
+    fn with_radical(n: Integer, q: Rational) -> Result<Expression, MathError> {
+        // `0^q` is 0 for a positive q and a division by zero for a negative one.
+        if n.num().is_zero() {
+            return match q.num().is_positive() {
+                true => Ok(int!(0)),
+                false => Err(MathError::Undefined("Indeterminate form: k/0".to_string())),
+            }
+        }
+
+        if q.den().is_even() && n.num().is_negative() {
+            if !settings::complex_mode() {
+                return Err(MathError::Undefined("Negative value under even root".to_string()))
+            }
+
+            // (-n)^(p/q) = (-1)^(p/q) * n^(p/q), where (-1)^(p/2) = i^p.
+            let root = Power::with_radical(Integer::new(-n.num()), q.clone())?;
+            let unit = match q.den() == &BigInt::from(2) {
+                true => Power::with_integer_exp(imag!(), Integer::new(q.num().clone()))?,
+                false => pow!(int!(-1), q.into()),
+            };
+            return Product::with_two_args(root, unit)
+        }
+
+        // In real mode odd roots of negative numbers take their real value.
+        if n.num().is_negative() && !settings::complex_mode() {
+            let root = Power::with_radical(Integer::new(-n.num()), q.clone())?;
+            return match q.num().is_odd() {
+                true => Product::with_two_args(int!(-1), root),
+                false => Ok(root),
+            }
+        }
+
+        let Some(exp) = q.den().to_u32() else {
+            return Ok(pow!(n.into(), q.into()))
+        };
+
+        let mut outside_root = BigInt::one();
+        let mut inside_root = Integer::checked_pow(n.num(), q.num().magnitude())?;
+
+        // Trial division only pulls out small perfect powers, so large
+        // arguments are also checked for being a perfect power outright.
+        let root = inside_root.magnitude().nth_root(exp);
+        if root.pow(exp) == *inside_root.magnitude() {
+            outside_root = root.into();
+            inside_root = inside_root.signum();
         }
 
-        let mut outside_root = 1;
-        let mut inside_root = n.num();
-        let exp = q.den().unsigned_abs();
-        let mut d = 2i32;
+        let mut d = BigInt::from(2);
         let mut e = d.pow(exp);
 
-        while e <= inside_root.abs() {
-            if inside_root % e == 0 {
-                inside_root /= e;
-                outside_root *= d;
+        while e <= inside_root.abs() && d <= BigInt::from(RADICAL_TRIAL_LIMIT) {
+            if (&inside_root % &e).is_zero() {
+                inside_root /= &e;
+                outside_root *= &d;
             }
             else {
                 d += 1;
@@ -142,16 +385,18 @@ impl Power {
             }
         }
 
-        Product::with_two_args(
-            pow!(
-                int!(outside_root),
-                int!(q.num())
-            ).simplify()?,
-            pow!(
-                Power::with_integer_base(Integer::new(inside_root), int!(q.num()))?,
-                frac!(1, q.den())
-            )
-        )
+        let (outside, inside_exp) = match q.num().is_negative() {
+            true => (frac!(1, outside_root.clone()), frac!(-1, q.den().clone())),
+            false => (int!(outside_root.clone()), frac!(1, q.den().clone())),
+        };
+
+        // The result is built directly rather than through `Product::with_two_args`,
+        // which would merge equal integer bases straight back into this radical.
+        match inside_root {
+            i if i.is_one() => outside.simplify(),
+            i if outside_root.is_one() => Ok(pow!(int!(i), inside_exp)),
+            i => Ok(prod!(outside, pow!(int!(i), inside_exp))),
+        }
     }
 }
 
@@ -162,4 +407,35 @@ impl From<Expression> for Power {
             u => Power::new(u, int!(1))
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn diff(text: &str) -> Expression {
+        parse(text).derivative(&var!("x")).unwrap()
+    }
+
+    #[test]
+    fn power_rule_and_chain_rule() {
+        assert_eq!(diff("x^3"), parse("3x^2"));
+        assert_eq!(diff("1/x"), parse("-1/x^2"));
+        assert_eq!(diff("sqrt(x)"), parse("1/(2 sqrt(x))"));
+        assert_eq!(diff("sin(x)^2"), parse("2 sin(x) cos(x)"));
+        assert_eq!(diff("exp(2x)"), parse("2 exp(2x)"));
+    }
+
+    #[test]
+    fn product_rule_and_symbolic_exponents() {
+        assert_eq!(diff("x ln(x)"), parse("1 + ln(x)"));
+        assert_eq!(diff("x^x"), parse("(1 + ln(x)) x^x"));
+        assert_eq!(diff("y x^2"), parse("2 x y"));
+        assert_eq!(diff("atan(x)"), parse("1/(1 + x^2)"));
+    }
+
+    #[test]
+    fn higher_derivatives_repeat_the_rules() {
+        assert_eq!(diff("x^5").derivative(&var!("x")).unwrap().derivative(&var!("x")).unwrap(), parse("60x^2"));
+    }
+}
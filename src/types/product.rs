@@ -1,12 +1,19 @@
-use crate::expression::{Expression, UndefinedError};
-use crate::traits::Simplify;
-use crate::types::{self, Power, Integer, Rational, Sum, sum};
+use std::cmp;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+use num_bigint::Sign;
+use num_traits::One;
+
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::limit;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+use crate::types::{self, Power, Integer, Rational, Sum, Variable, Complex, Function, sum};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Product(Vec<Expression>);
 
 impl Simplify for Product {
-    fn simplify(mut self) -> Result<Expression, UndefinedError> {
+    fn simplify(mut self) -> Result<Expression, MathError> {
         self.0 = self.0
             .into_iter()
             .map(Expression::simplify)
@@ -14,22 +21,58 @@ impl Simplify for Product {
 
         self.0.sort();
 
+        if let Some(u) = Product::with_infinities(&self.0)? {
+            return Ok(u)
+        }
+
         if self.0.contains(&int!(0)) {
             return Ok(int!(0))
         }
         
-        match self.0.len() {
-            0 => Ok(int!(1)),
-            1 => Ok(self.take_last().unwrap()), 
+        let u = match self.0.len() {
+            0 => int!(1),
+            1 => self.take_last().unwrap(), 
             2 => Product::with_two_args(
                 self.take_last().unwrap(),
                 self.take_last().unwrap()
-            ),
+            )?,
             _ => Product::with_more_args(
                 self.take_last().unwrap(),
                 self.simplify()?.into()
-            )
+            )?
+        };
+
+        Product::absorb_orders(u)
+    }
+}
+
+impl Evaluate for Product {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        self.0.iter().try_fold(Float::from_int(1), |acc, u| Ok(acc.mul(&u.evaluate(prec)?, prec)))
+    }
+}
+
+impl Expand for Product {
+    fn expand(self) -> Result<Expression, MathError> {
+        self.0
+            .into_iter()
+            .try_fold(int!(1), |acc, u| Product::expand_product(acc, u.expand()?))
+    }
+}
+
+impl Differentiate for Product {
+    // The product rule, differentiating one factor at a time.
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        let mut terms = Vec::new();
+        for (k, u) in self.0.iter().enumerate() {
+            if u.free_of(x) {
+                continue;
+            }
+            let mut factors = self.0.clone();
+            factors[k] = u.derivative(x)?;
+            terms.push(Product::new(factors).into());
         }
+        Sum::new(terms).simplify()
     }
 }
 
@@ -66,9 +109,40 @@ impl Product {
         self
     }
 
-    pub fn with_two_args(u1: Expression, u2: Expression) -> Result<Expression, UndefinedError> {
+    // A nonzero number times `oo` is `oo` with the sign of the number, while
+    // `0*oo` has no value. Products with other factors, such as `x*oo`, are
+    // left alone.
+    fn with_infinities(values: &[Expression]) -> Result<Option<Expression>, MathError> {
+        if !values.iter().any(|v| limit::infinity_sign(v).is_some()) {
+            return Ok(None)
+        }
+
+        let mut sign = cmp::Ordering::Greater;
+        for v in values {
+            let s = match v {
+                Expression::Integer(n) => n.num().sign().cmp(&Sign::NoSign),
+                Expression::Rational(q) => q.num().sign().cmp(&Sign::NoSign),
+                v => match limit::infinity_sign(v) {
+                    Some(s) => s,
+                    None => return Ok(None),
+                },
+            };
+            match s {
+                cmp::Ordering::Equal => return Err(MathError::Undefined("Indeterminate form: 0*oo".to_string())),
+                cmp::Ordering::Less => sign = sign.reverse(),
+                cmp::Ordering::Greater => (),
+            }
+        }
+
+        match sign {
+            cmp::Ordering::Greater => Ok(Some(var!(limit::INFINITY))),
+            _ => Ok(Some(Product(vec![int!(-1), var!(limit::INFINITY)]).into())),
+        }
+    }
+
+    pub fn with_two_args(u1: Expression, u2: Expression) -> Result<Expression, MathError> {
         match (u1, u2) {
-            (Expression::Integer(n), q) | (q, Expression::Integer(n)) if n.num() == 1
+            (Expression::Integer(n), q) | (q, Expression::Integer(n)) if n.num().is_one()
                 => Ok(q),
             
             (Expression::Integer(n), Expression::Integer(m))
@@ -78,7 +152,19 @@ impl Product {
                 => frac!(p.num() * q.num(), p.den() * q.den()).simplify(),
             
             (Expression::Integer(n), Expression::Rational(p)) | (Expression::Rational(p), Expression::Integer(n))
-                => frac!(p.num() * n.num(), p.den()).simplify(),
+                => frac!(p.num() * n.num(), p.den().clone()).simplify(),
+
+            // Gaussian rationals multiply out to a single `a + b*i`.
+            (u1 @ Expression::Sum(_), u2) | (u2, u1 @ Expression::Sum(_))
+                if Complex::gaussian(&u1).is_some() && Complex::gaussian(&u2).is_some()
+                => Complex::gaussian(&u1).unwrap().mul(Complex::gaussian(&u2).unwrap()).into_expression(),
+
+            (Expression::Function(Function::Exp(a)), Expression::Function(Function::Exp(b)))
+                => func!("exp"; sum!(*a, *b)).simplify(),
+
+            (Expression::Function(Function::Exp(a)), Expression::Variable(v))
+            | (Expression::Variable(v), Expression::Function(Function::Exp(a))) if v.as_str() == "e"
+                => func!("exp"; sum!(*a, int!(1))).simplify(),
 
             (u1, u2) if u1.base() == u2.base() => {
                 let p = Power::from(u1);
@@ -87,10 +173,10 @@ impl Product {
             }
 
             (Expression::Product(p), Expression::Product(q))
-                => Product::merge_products(p, q).map(Expression::from),
+                => Product::merge_products(p, q).map(Product::into_simplified),
 
             (Expression::Product(p), u) | (u, Expression::Product(p))
-                => Product::merge_products(p, u.into()).map(Expression::from),
+                => Product::merge_products(p, u.into()).map(Product::into_simplified),
 
             (u1, u2) if u2 < u1
                 => Ok(prod!(u2, u1)),
@@ -100,22 +186,59 @@ impl Product {
         }
     }
 
-    fn with_more_args(u0: Expression, p: Product) -> Result<Expression, UndefinedError> {
-        let mut result = Product::merge_products(p, u0.into())?;
+    // Multiplies two expanded expressions, distributing over any sums.
+    pub fn expand_product(r: Expression, s: Expression) -> Result<Expression, MathError> {
+        match (r, s) {
+            (Expression::Sum(r), s) => Sum::new(r.values()
+                .iter()
+                .map(|v| Product::expand_product(v.clone(), s.clone()))
+                .collect::<Result<Vec<_>, _>>()?
+            ).simplify(),
 
-        match result.0.len() {
-            0 => Ok(int!(1)),
-            1 => Ok(result.take_last().unwrap()),
-            _ => Ok(result.into())
+            (r, s @ Expression::Sum(_))
+                => Product::expand_product(s, r),
+
+            (r, s)
+                => prod!(r, s).simplify(),
+        }
+    }
+
+    fn with_more_args(u0: Expression, p: Product) -> Result<Expression, MathError> {
+        Product::merge_products(p, u0.into()).map(Product::into_simplified)
+    }
+
+    // Unwraps merged products that were left with fewer than two factors.
+    fn into_simplified(mut self) -> Expression {
+        match self.0.len() {
+            0 => int!(1),
+            1 => self.take_last().unwrap(),
+            _ => self.into()
         }
     }
 
-    fn merge_products(mut p: Product, mut q: Product) -> Result<Product, UndefinedError> {
+    // Multiplies every other factor into the argument of an order term.
+    fn absorb_orders(u: Expression) -> Result<Expression, MathError> {
+        let Expression::Product(p) = &u else { return Ok(u) };
+        if !p.0.iter().any(|v| matches!(v, Expression::Function(Function::Order(_)))) {
+            return Ok(u)
+        }
+
+        let factors = p.0
+            .iter()
+            .map(|v| match v {
+                Expression::Function(Function::Order(g)) => g.as_ref().clone(),
+                v => v.clone(),
+            })
+            .collect();
+        func!("O"; Product::new(factors).into()).simplify()
+    }
+
+    fn merge_products(mut p: Product, mut q: Product) -> Result<Product, MathError> {
         let Some(p1) = p.take_last() else { return Ok(q) };
         let Some(q1) = q.take_last() else { return Ok(p.adjoin(p1)) };
 
         match Product::with_two_args(p1.clone(), q1.clone())? {
-            Expression::Integer(n) if n.num() == 1 => Product::merge_products(p, q),
+            Expression::Integer(n) if n.num().is_one() => Product::merge_products(p, q),
             Expression::Product(u) if (u.0.first().unwrap(), u.0.last().unwrap()) == (&p1, &q1) 
                 => Ok(Product::merge_products(p.adjoin(p1), q)?.adjoin(q1)),
 
@@ -127,6 +250,20 @@ impl Product {
     }
 }
 
+// Operands are compared starting from the last, most significant one, so
+// that terms differing only in their coefficient sort next to each other.
+impl cmp::Ord for Product {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl cmp::PartialOrd for Product {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<Expression> for Product {
     fn from(value: Expression) -> Self {
         match value {
@@ -134,4 +271,32 @@ impl From<Expression> for Product {
             u => Product(vec![u]),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn expand(text: &str) -> Result<Expression, MathError> {
+        parse(text).expand()
+    }
+
+    #[test]
+    fn expand_distributes_products_over_sums() {
+        assert_eq!(expand("(x + y)(x - y)").unwrap(), parse("x^2 - y^2"));
+        assert_eq!(expand("x*(2 + 3x)").unwrap(), parse("2x + 3x^2"));
+        assert_eq!(expand("(a + b)^2 (a - b)").unwrap(), parse("a^3 + a^2 b - a b^2 - b^3"));
+    }
+
+    #[test]
+    fn expand_multiplies_out_powers_of_sums() {
+        assert_eq!(expand("(x + 1)^3").unwrap(), parse("x^3 + 3x^2 + 3x + 1"));
+        assert_eq!(expand("(x - 1)^2 - (x + 1)^2").unwrap(), parse("-4x"));
+        assert_eq!(expand("1/(x + 1)").unwrap(), parse("1/(x + 1)"));
+    }
+
+    #[test]
+    fn expand_limits_the_number_of_terms() {
+        assert!(matches!(expand("(x + 1)^100000"), Err(MathError::Overflow(_))));
+    }
+}
@@ -1,24 +1,38 @@
 use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
 
-use crate::expression::{Expression, UndefinedError};
-use crate::traits::Simplify;
+use num_bigint::BigInt;
+use num_integer::Integer as _;
+use num_traits::{Signed, Zero};
+
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::traits::{Evaluate, Simplify};
 use crate::types::Integer;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Rational(i32, i32);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational(BigInt, BigInt);
 
 impl Simplify for Rational {
-    fn simplify(self) -> Result<Expression, UndefinedError> {
+    fn simplify(self) -> Result<Expression, MathError> {
         let gcd = self.gcd();
         match (self.0, self.1) {
-            (_, 0)                         => Err(UndefinedError("Indeterminate form: k/0".to_string())),
-            (n, d) if n % d == 0 => Ok(int!(n / d)),
-            (n, d) if d < 0      => Ok(frac!(-n / gcd, -d / gcd)),
-            (n, d)               => Ok(frac!(n / gcd, d / gcd)),
+            (_, d) if d.is_zero()                => Err(MathError::Undefined("Indeterminate form: k/0".to_string())),
+            (n, d) if (&n % &d).is_zero() => Ok(int!(n / d)),
+            (n, d) if d.is_negative()      => Ok(frac!(-n / &gcd, -d / &gcd)),
+            (n, d)                         => Ok(frac!(n / &gcd, d / &gcd)),
         }
     }
 }
 
+impl Evaluate for Rational {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        Float::from_ratio(self.num(), self.den(), prec)
+    }
+}
+
 impl cmp::Ord for Rational {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         (self.num() * other.den()).cmp(&(self.den() * other.num()))
@@ -32,40 +46,83 @@ impl cmp::PartialOrd for Rational {
 }
 
 impl Rational {
-    pub fn new(num: i32, den: i32) -> Rational {
-        Rational(num, den)
+    pub fn new(num: impl Into<BigInt>, den: impl Into<BigInt>) -> Rational {
+        Rational(num.into(), den.into())
     }
 
-    pub fn gcd(&self) -> i32 {
-        let mut a = self.num();
-        let mut b = self.den();
+    pub fn gcd(&self) -> BigInt {
+        self.num().gcd(self.den())
+    }
 
-        while b != 0 {
-            (a, b) = (b, a % b);
-        }
-        a.abs()
+    pub fn num(&self) -> &BigInt {
+        &self.0
     }
 
-    pub fn num(&self) -> i32 {
-        self.0
+    pub fn den(&self) -> &BigInt {
+        &self.1
     }
+}
+
+#[derive(Debug)]
+pub struct ParseRationalError(String);
+
+impl Error for ParseRationalError {}
 
-    pub fn den(&self) -> i32 {
-        self.1
+impl fmt::Display for ParseRationalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-impl From<f32> for Rational {
-    fn from(value: f32) -> Self {
-        const EPSILON: f32 = 1e-10;
-        let mut den = 1.0;
+// Parses a decimal literal such as `123.456`, `1.5e-3` or `0.1(6)` into
+// the exact rational it denotes, where digits in parentheses repeat forever.
+impl FromStr for Rational {
+    type Err = ParseRationalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseRationalError(format!("Invalid number syntax `{}`", s));
+
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((m, e)) => (m, e.parse::<BigInt>().map_err(|_| invalid())?),
+            None => (s, BigInt::zero()),
+        };
 
-        while (value * den) % 1.0 > EPSILON {
-            den *= 10.0;
+        let (digits, repeating) = match mantissa.split_once('(') {
+            Some((d, r)) => (d, r.strip_suffix(')').ok_or_else(invalid)?),
+            None => (mantissa, ""),
+        };
+
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let is_digits = |t: &str| t.chars().all(|c| c.is_ascii_digit());
+        if !is_digits(whole) || !is_digits(fraction) || !is_digits(repeating)
+            || whole.is_empty() && fraction.is_empty() && repeating.is_empty()
+            || !repeating.is_empty() && !digits.contains('.') {
+            return Err(invalid())
+        }
+
+        let ten = BigInt::from(10);
+        let digit_value = |t: &str| t.parse::<BigInt>().unwrap_or_default();
+
+        let mut num = digit_value(&format!("{}{}", whole, fraction));
+        let mut den = ten.pow(fraction.len() as u32);
+
+        // A repeating block of k digits r adds r / (10^k - 1) in the
+        // position right after the fixed fraction digits.
+        if !repeating.is_empty() {
+            let period = ten.pow(repeating.len() as u32) - 1;
+            num = num * &period + digit_value(repeating);
+            den *= period;
         }
 
-        let num = (value * den).round() as i32;
-        let den = den as i32;
-        Rational::new(num, den)
+        let scale = Integer::checked_pow(&ten, exponent.magnitude())
+            .map_err(|e| ParseRationalError(e.to_string()))?;
+
+        match exponent.is_negative() {
+            true => den *= scale,
+            false => num *= scale,
+        }
+
+        Ok(Rational::new(num, den))
     }
-}
\ No newline at end of file
+}
@@ -1,31 +1,69 @@
-use crate::expression::{Expression, UndefinedError};
-use crate::traits::Simplify;
-use crate::types::{self, Integer, Rational, Product};
+use std::cmp;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+use num_traits::Zero;
+
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::limit;
+use crate::traits::{Differentiate, Evaluate, Expand, Simplify};
+use crate::types::{self, Integer, Rational, Product, Variable, Function};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Sum(Vec<Expression>);
 
 impl Simplify for Sum {
-    fn simplify(mut self) -> Result<Expression, UndefinedError> {
+    fn simplify(mut self) -> Result<Expression, MathError> {
         self.0 = self.0
             .into_iter()
             .map(Expression::simplify)
             .collect::<Result<Vec<_>, _>>()?;
 
         self.0.sort();
+
+        if let Some(u) = Sum::with_infinities(&self.0)? {
+            return Ok(u)
+        }
         
-        match self.0.len() {
-            0 => Ok(int!(1)),
-            1 => Ok(self.take_last().unwrap()), 
+        let u = match self.0.len() {
+            0 => int!(0),
+            1 => self.take_last().unwrap(), 
             2 => Sum::with_two_args(
                 self.take_last().unwrap(),
                 self.take_last().unwrap()
-            ),
+            )?,
             _ => Sum::with_more_args(
                 self.take_last().unwrap(),
                 self.simplify()?.into()
-            )
-        }
+            )?
+        };
+
+        Ok(Sum::absorb_orders(u))
+    }
+}
+
+impl Evaluate for Sum {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        self.0.iter().try_fold(Float::from_int(0), |acc, u| Ok(acc.add(&u.evaluate(prec)?, prec)))
+    }
+}
+
+impl Expand for Sum {
+    fn expand(self) -> Result<Expression, MathError> {
+        Sum::new(self.0
+            .into_iter()
+            .map(Expression::expand)
+            .collect::<Result<Vec<_>, _>>()?
+        ).simplify()
+    }
+}
+
+impl Differentiate for Sum {
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        Sum::new(self.0
+            .iter()
+            .map(|u| u.derivative(x))
+            .collect::<Result<Vec<_>, _>>()?
+        ).simplify()
     }
 }
 
@@ -47,9 +85,9 @@ impl Sum {
         self
     }
 
-    fn with_two_args(u1: Expression, u2: Expression) -> Result<Expression, UndefinedError> {
+    fn with_two_args(u1: Expression, u2: Expression) -> Result<Expression, MathError> {
         match (u1, u2) {
-            (Expression::Integer(n), q) | (q, Expression::Integer(n)) if n.num() == 0
+            (Expression::Integer(n), q) | (q, Expression::Integer(n)) if n.num().is_zero()
                 => Ok(q),
             
             (Expression::Integer(n), Expression::Integer(m))
@@ -59,7 +97,7 @@ impl Sum {
                 => frac!(p.num()*q.den() + q.num()*p.den(), p.den() * q.den()).simplify(),
             
             (Expression::Integer(n), Expression::Rational(p)) | (Expression::Rational(p), Expression::Integer(n))
-                => frac!(n.num()*p.den() + p.num(), p.den()).simplify(),
+                => frac!(n.num()*p.den() + p.num(), p.den().clone()).simplify(),
 
             (u1, u2) if u1.term() == u2.term() => {
                 let p = Product::from(u1);
@@ -72,10 +110,10 @@ impl Sum {
             }
 
             (Expression::Sum(p), Expression::Sum(q))
-                => Sum::merge_sums(p, q).map(Expression::from),
+                => Sum::merge_sums(p, q).map(Sum::into_simplified),
 
             (Expression::Sum(p), u) | (u, Expression::Sum(p))
-                => Sum::merge_sums(p, u.into()).map(Expression::from),
+                => Sum::merge_sums(p, u.into()).map(Sum::into_simplified),
 
             (u1, u2) if u2 < u1
                 => Ok(sum!(u2, u1)),
@@ -85,21 +123,64 @@ impl Sum {
         }
     }
 
-    fn with_more_args(u0: Expression, p: Sum) -> Result<Expression, UndefinedError> {
-        let mut result = Sum::merge_sums(p, u0.into())?;
+    fn with_more_args(u0: Expression, p: Sum) -> Result<Expression, MathError> {
+        Sum::merge_sums(p, u0.into()).map(Sum::into_simplified)
+    }
 
-        match result.0.len() {
-            0 => Ok(int!(0)),
-            1 => Ok(result.take_last().unwrap()),
-            _ => Ok(result.into())
+    // Unwraps merged sums that were left with fewer than two terms.
+    fn into_simplified(mut self) -> Expression {
+        match self.0.len() {
+            0 => int!(0),
+            1 => self.take_last().unwrap(),
+            _ => self.into()
         }
     }
 
-    fn merge_sums(mut p: Sum, mut q: Sum) -> Result<Sum, UndefinedError> {
+    // `oo` absorbs the finite terms of a sum, while `oo - oo` has no value.
+    // Sums with terms such as `x*oo`, whose sign is unknown, are left alone.
+    fn with_infinities(values: &[Expression]) -> Result<Option<Expression>, MathError> {
+        let Some(infinity) = values.iter().find(|v| limit::infinity_sign(v).is_some()) else {
+            return Ok(None)
+        };
+
+        if values.iter().any(|v| limit::infinity_sign(v).is_some_and(|s| Some(s) != limit::infinity_sign(infinity))) {
+            return Err(MathError::Undefined("Indeterminate form: oo - oo".to_string()))
+        }
+        if values.iter().any(|v| limit::infinity_sign(v).is_none() && !v.free_of(&var!(limit::INFINITY))) {
+            return Ok(None)
+        }
+        Ok(Some(infinity.clone()))
+    }
+
+    // Drops the terms that an order term of the sum already accounts for.
+    fn absorb_orders(u: Expression) -> Expression {
+        let Expression::Sum(s) = &u else { return u };
+        let orders: Vec<_> = s.0
+            .iter()
+            .filter_map(|v| match v {
+                Expression::Function(Function::Order(g)) => Some(g.as_ref()),
+                _ => None,
+            })
+            .collect();
+
+        if orders.is_empty() {
+            return u
+        }
+
+        let is_order_of = |v: &Expression, g: &Expression| matches!(v, Expression::Function(Function::Order(h)) if h.as_ref() == g);
+        Sum(s.0
+            .iter()
+            .filter(|v| !orders.iter().any(|g| Function::order_absorbs(g, v) && !is_order_of(v, g)))
+            .cloned()
+            .collect()
+        ).into_simplified()
+    }
+
+    fn merge_sums(mut p: Sum, mut q: Sum) -> Result<Sum, MathError> {
         let Some(p1) = p.take_last() else { return Ok(q) };
         let Some(q1) = q.take_last() else { return Ok(p.adjoin(p1)) };
         match Sum::with_two_args(p1.clone(), q1.clone())? {
-            Expression::Integer(n) if n.num() == 0 => Sum::merge_sums(p, q),
+            Expression::Integer(n) if n.num().is_zero() => Sum::merge_sums(p, q),
 
             Expression::Sum(u) if (u.0.first().unwrap(), u.0.last().unwrap()) == (&p1, &q1) 
                 => Ok(Sum::merge_sums(p.adjoin(p1), q)?.adjoin(q1)),
@@ -112,6 +193,20 @@ impl Sum {
     }
 }
 
+// Operands are compared starting from the last, most significant one, so
+// that terms differing only in their coefficient sort next to each other.
+impl cmp::Ord for Sum {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl cmp::PartialOrd for Sum {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<Expression> for Sum {
     fn from(value: Expression) -> Self {
         match value {
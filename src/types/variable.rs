@@ -1,17 +1,40 @@
 use std::rc::Rc;
 
-use crate::expression::{Expression, UndefinedError};
-use crate::traits::Simplify;
+use crate::expression::{Expression, MathError};
+use crate::float::Float;
+use crate::settings::{self, Assumptions};
+use crate::traits::{Differentiate, Evaluate, Simplify};
+use crate::types::Integer;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct Variable(Box<str>);
 
 impl Simplify for Variable {
-    fn simplify(self) -> Result<Expression, UndefinedError> {
+    fn simplify(self) -> Result<Expression, MathError> {
         Ok(self.into())
     }
 }
 
+impl Evaluate for Variable {
+    fn evaluate(&self, prec: u64) -> Result<Float, MathError> {
+        match self.as_str() {
+            "e" => Ok(Float::e(prec)),
+            "pi" => Ok(Float::pi(prec)),
+            "oo" => Err(MathError::Undefined("Cannot evaluate infinity".to_string())),
+            name => Err(MathError::Undefined(format!("Cannot evaluate free variable `{}`", name)))
+        }
+    }
+}
+
+impl Differentiate for Variable {
+    fn derivative(&self, x: &Expression) -> Result<Expression, MathError> {
+        match x {
+            Expression::Variable(v) if v == self => Ok(int!(1)),
+            _ => Ok(int!(0)),
+        }
+    }
+}
+
 impl Variable {
     pub fn new(name: impl Into<String>) -> Variable {
         Variable(Box::from(name.into()))
@@ -20,4 +43,9 @@ impl Variable {
     pub fn as_str(&self) -> &str {
         self.0.as_ref()
     }
+
+    // What has been declared about the variable with `assume`.
+    pub fn assumptions(&self) -> Assumptions {
+        settings::assumptions(self.as_str())
+    }
 }